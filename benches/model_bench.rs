@@ -0,0 +1,240 @@
+//! Benchmarks for converting compound documents into models. Documents are
+//! generated with a configurable number of primary and included resources so
+//! that the cost of resolving relationships through `included` can be compared
+//! as documents grow.
+#[macro_use]
+extern crate criterion;
+#[macro_use]
+extern crate jsonapi;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use criterion::{BatchSize, BenchmarkId, Criterion};
+use jsonapi::document::CollectionDocument;
+use jsonapi::model::*;
+use serde_json::{from_value, to_value, Map, Value};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Article {
+    id: String,
+    title: String,
+    author: Person,
+    comments: Vec<Comment>,
+}
+jsonapi_model!(Article; "articles"; has one author; has many comments);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Person {
+    id: String,
+    name: String,
+}
+jsonapi_model!(Person; "people");
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Comment {
+    id: String,
+    body: String,
+}
+jsonapi_model!(Comment; "comments");
+
+const COMMENTS_PER_ARTICLE: usize = 4;
+
+/// A collection document with `articles` primary resources, each with its own
+/// author and `COMMENTS_PER_ARTICLE` comments in `included`
+fn generate_document(articles: usize) -> DocumentData {
+    let articles: Vec<Article> = (0..articles)
+        .map(|i| Article {
+            id: i.to_string(),
+            title: format!("Article {}", i),
+            author: Person {
+                id: i.to_string(),
+                name: format!("Author {}", i),
+            },
+            comments: (0..COMMENTS_PER_ARTICLE)
+                .map(|c| Comment {
+                    id: format!("{}-{}", i, c),
+                    body: format!("Comment {} on article {}", c, i),
+                })
+                .collect(),
+        })
+        .collect();
+
+    match vec_to_jsonapi_document(articles) {
+        JsonApiDocument::Data(doc) => doc,
+        JsonApiDocument::Error(_) => unreachable!(),
+    }
+}
+
+fn identifiers(doc: &DocumentData) -> Vec<ResourceIdentifier> {
+    doc.included
+        .iter()
+        .flatten()
+        .map(|r| ResourceIdentifier {
+            _type: r._type.clone(),
+            id: r.id.clone(),
+        })
+        .collect()
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("included lookup");
+    for size in [10, 100, 1000].iter() {
+        let doc = generate_document(*size);
+        let included = doc.included.as_ref().unwrap();
+        let needles = identifiers(&doc);
+
+        group.bench_with_input(BenchmarkId::new("linear scan", size), &needles, |b, needles| {
+            b.iter(|| {
+                for needle in needles {
+                    criterion::black_box(legacy_lookup(needle, included));
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("index", size), &needles, |b, needles| {
+            b.iter(|| {
+                let index = IncludedIndex::new(included);
+                for needle in needles {
+                    criterion::black_box(index.get(needle));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_from_jsonapi_document(c: &mut Criterion) {
+    let mut group = c.benchmark_group("CollectionDocument::from_document_data_with");
+    group.sample_size(20);
+    let ctx = ConversionContext::default();
+    for size in [10, 100, 1000].iter() {
+        let doc = generate_document(*size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &doc, |b, doc| {
+            b.iter_batched(
+                || doc.clone(),
+                |doc| criterion::black_box(CollectionDocument::<Article>::from_document_data_with(doc, &ctx).unwrap()),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// The lookup used before `IncludedIndex`: a scan of the included resources
+fn legacy_lookup<'a>(needle: &ResourceIdentifier, haystack: &'a [Resource]) -> Option<&'a Resource> {
+    haystack
+        .iter()
+        .find(|resource| resource._type == needle._type && resource.id == needle.id)
+}
+
+/// The conversion used before models were serialized directly: the whole model
+/// is turned into a `Value`, and `id` and the relationship fields are removed
+/// from it again. Members are named, and links and included resources built,
+/// as `to_jsonapi_resource` does.
+fn legacy_to_jsonapi_resource<M: JsonApiModel>(model: &M, ctx: &ConversionContext) -> (Resource, Option<Resources>) {
+    let naming = M::naming_policy().unwrap_or(ctx.naming);
+    let mut attrs = match to_value(model).unwrap() {
+        Value::Object(attrs) => attrs,
+        _ => unreachable!(),
+    };
+    let _ = attrs.remove("id");
+    let fields = M::relationship_fields().unwrap_or(&[]);
+    let attributes = attrs
+        .into_iter()
+        .filter(|(key, _)| !fields.contains(&key.as_str()))
+        .map(|(key, value)| (naming.apply(&key), value))
+        .collect();
+    let relationships = model.build_relationships().map(|relationships| {
+        relationships
+            .into_iter()
+            .map(|(name, mut relationship)| {
+                let name = naming.apply(&name);
+                relationship.links = model.jsonapi_relationship_links(&name, ctx);
+                (name, relationship)
+            })
+            .collect()
+    });
+    let resource = Resource {
+        _type: model.jsonapi_type(),
+        id: model.jsonapi_id(),
+        relationships,
+        attributes,
+        links: model.jsonapi_links(ctx),
+        meta: model.jsonapi_meta(),
+    };
+    (resource, model.build_included(ctx).unwrap())
+}
+
+/// The conversion used before models were deserialized directly: the resource
+/// and, recursively, its related resources are turned into a `Value` that the
+/// model deserializes from. Relationships already visited are not followed
+/// again.
+fn legacy_resource_to_attrs(resource: &Resource, included: Option<&IncludedIndex>, visited: &[&str]) -> Map<String, Value> {
+    let mut attrs: Map<String, Value> = resource.attributes.clone().into_iter().collect();
+    attrs.insert("id".into(), resource.id.clone().into());
+
+    let mut visited = visited.to_vec();
+    if let (Some(relationships), Some(index)) = (resource.relationships.as_ref(), included) {
+        for (name, relationship) in relationships {
+            if visited.contains(&name.as_str()) {
+                return attrs;
+            }
+            visited.push(name);
+
+            let related = |identifier| {
+                index
+                    .get(identifier)
+                    .map(|r| Value::Object(legacy_resource_to_attrs(r, included, &visited)))
+                    .unwrap_or(Value::Null)
+            };
+            let value = match relationship.data {
+                Some(IdentifierData::Single(ref identifier)) => related(identifier),
+                Some(IdentifierData::Multiple(ref identifiers)) => identifiers.iter().map(related).collect(),
+                Some(IdentifierData::None) | None => Value::Null,
+            };
+            attrs.insert(name.to_string(), value);
+        }
+    }
+    attrs
+}
+
+fn bench_model_conversion(c: &mut Criterion) {
+    let doc = generate_document(1);
+    let included = doc.included.clone();
+    let resource = match doc.data {
+        Some(PrimaryData::Multiple(ref resources)) => resources[0].clone(),
+        _ => unreachable!(),
+    };
+    let article = Article::from_jsonapi_resource(&resource, &included).unwrap();
+    let ctx = ConversionContext::default();
+    // Both paths must do the same work for the comparison to mean anything
+    assert_eq!(legacy_to_jsonapi_resource(&article, &ctx), article.try_to_jsonapi_resource_with(&ctx).unwrap());
+    let index = included.as_ref().map(|inc| IncludedIndex::new(inc));
+    let attrs = legacy_resource_to_attrs(&resource, index.as_ref(), &[]);
+    assert_eq!(from_value::<Article>(Value::Object(attrs)).unwrap(), article);
+
+    let mut group = c.benchmark_group("to_jsonapi_resource");
+    group.bench_function("to_value", |b| {
+        b.iter(|| criterion::black_box(legacy_to_jsonapi_resource(&article, &ctx)))
+    });
+    group.bench_function("serializer", |b| {
+        b.iter(|| criterion::black_box(article.try_to_jsonapi_resource_with(&ctx).unwrap()))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("from_jsonapi_resource");
+    group.bench_function("from_value", |b| {
+        b.iter(|| {
+            let index = included.as_ref().map(|inc| IncludedIndex::new(inc));
+            let attrs = legacy_resource_to_attrs(&resource, index.as_ref(), &[]);
+            criterion::black_box(from_value::<Article>(Value::Object(attrs)).unwrap())
+        })
+    });
+    group.bench_function("deserializer", |b| {
+        b.iter(|| criterion::black_box(Article::from_jsonapi_resource(&resource, &included).unwrap()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_lookup, bench_from_jsonapi_document, bench_model_conversion);
+criterion_main!(benches);
@@ -1,8 +1,13 @@
 //! Defines custom types and structs primarily that composite the JSON:API
 //! document
 use serde_json;
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{Deserialize, DeserializeOwned, Deserializer, MapAccess, SeqAccess, Visitor};
 use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
 use crate::errors::*;
+use crate::pointer;
 use std::str::FromStr;
 use std;
 
@@ -14,7 +19,9 @@ pub type Resources = Vec<Resource>;
 /// Vector of `ResourceIdentifiers`
 pub type ResourceIdentifiers = Vec<ResourceIdentifier>;
 pub type Links = HashMap<String, JsonApiValue>;
-/// Meta-data object, can contain any data
+/// Meta-data object, can contain any data. Resources and documents are
+/// aliases of `Generic` types with this map as their `meta`; any type that
+/// (de)serializes as a JSON object can be used instead
 pub type Meta = HashMap<String, JsonApiValue>;
 /// Resource Attributes, can be any JSON value
 pub type ResourceAttributes = HashMap<String, JsonApiValue>;
@@ -36,11 +43,11 @@ pub struct ResourceIdentifier {
     pub id: JsonApiId,
 }
 
-/// Representation of a JSON:API resource. This is a struct that contains
-/// attributes that map to the JSON:API specification of `id`, `type`,
-/// `attributes`, `relationships`, `links`, and `meta`
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
-pub struct Resource {
+/// Representation of a JSON:API resource, with a `meta` of type `M`. This is
+/// a struct that contains attributes that map to the JSON:API specification
+/// of `id`, `type`, `attributes`, `relationships`, `links`, and `meta`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GenericResource<M> {
     #[serde(rename = "type")]
     pub _type: String,
     pub id: JsonApiId,
@@ -51,29 +58,36 @@ pub struct Resource {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub links: Option<Links>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub meta: Option<Meta>,
+    pub meta: Option<M>,
 }
 
+/// A resource with a [`Meta`](type.Meta.html) map as its `meta`
+pub type Resource = GenericResource<Meta>;
+
 /// Relationship with another object
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Relationship {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, deserialize_with = "deserialize_linkage", skip_serializing_if = "Option::is_none")]
     pub data: Option<IdentifierData>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub links: Option<Links>,
 }
 
-/// Valid data Resource (can be None)
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// Valid data Resource (can be None), of resources with a `meta` of type `M`
+#[derive(Serialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
-pub enum PrimaryData {
+pub enum GenericPrimaryData<M> {
     None,
-    Single(Box<Resource>),
-    Multiple(Resources),
+    Single(Box<GenericResource<M>>),
+    Multiple(Vec<GenericResource<M>>),
 }
 
+/// Primary data of resources with a [`Meta`](type.Meta.html) map as their
+/// `meta`
+pub type PrimaryData = GenericPrimaryData<Meta>;
+
 /// Valid Resource Identifier (can be None)
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum IdentifierData {
     None,
@@ -81,29 +95,93 @@ pub enum IdentifierData {
     Multiple(ResourceIdentifiers),
 }
 
-/// A struct that defines an error state for a JSON:API document
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
-pub struct DocumentError {
+/// Deserializes `null`, an object or an array of objects into the `None`,
+/// `Single` or `Multiple` variant. Unlike an untagged enum this keeps the
+/// deserializer of the document, so errors in the objects point at them.
+macro_rules! deserialize_one_or_many {
+    ($data:ident<$($param:ident),*>, $item:ident, $expecting:expr, $single:expr) => {
+        impl<'de, $($param: Deserialize<'de>),*> Deserialize<'de> for $data<$($param),*> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+                struct DataVisitor<$($param),*>(PhantomData<($($param,)*)>);
+
+                impl<'de, $($param: Deserialize<'de>),*> Visitor<'de> for DataVisitor<$($param),*> {
+                    type Value = $data<$($param),*>;
+
+                    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        f.write_str($expecting)
+                    }
+
+                    fn visit_unit<E>(self) -> std::result::Result<Self::Value, E> {
+                        Ok($data::None)
+                    }
+
+                    fn visit_none<E>(self) -> std::result::Result<Self::Value, E> {
+                        Ok($data::None)
+                    }
+
+                    fn visit_map<A: MapAccess<'de>>(self, map: A) -> std::result::Result<Self::Value, A::Error> {
+                        $item::deserialize(MapAccessDeserializer::new(map)).map($single)
+                    }
+
+                    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> std::result::Result<Self::Value, A::Error> {
+                        Vec::deserialize(SeqAccessDeserializer::new(seq)).map($data::Multiple)
+                    }
+                }
+
+                deserializer.deserialize_any(DataVisitor(PhantomData))
+            }
+        }
+    };
+}
+
+deserialize_one_or_many!(
+    GenericPrimaryData<M>,
+    GenericResource,
+    "null, a resource object or an array of resource objects",
+    |resource| GenericPrimaryData::Single(Box::new(resource))
+);
+deserialize_one_or_many!(
+    IdentifierData<>,
+    ResourceIdentifier,
+    "null, a resource identifier or an array of resource identifiers",
+    IdentifierData::Single
+);
+
+/// Reads `"data": null` as empty linkage, which is `Some(IdentifierData::None)`
+/// like the linkage of models, and keeps `None` for a relationship without data
+fn deserialize_linkage<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<IdentifierData>, D::Error> {
+    IdentifierData::deserialize(deserializer).map(Some)
+}
+
+/// A struct that defines an error state for a JSON:API document, with a
+/// top-level `meta` of type `M`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GenericDocumentError<M> {
     pub errors: JsonApiErrors,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub links: Option<Links>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub meta: Option<Meta>,
+    pub meta: Option<M>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub jsonapi: Option<JsonApiInfo>,
 }
 
-/// A struct that defines properties for a JSON:API document that contains no errors
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
-pub struct DocumentData {
+/// An error document with a [`Meta`](type.Meta.html) map as its `meta`
+pub type DocumentError = GenericDocumentError<Meta>;
+
+/// A struct that defines properties for a JSON:API document that contains no
+/// errors, with a top-level `meta` of type `M` and resources with a `meta` of
+/// type `R`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GenericDocumentData<M, R = M> {
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub data: Option<PrimaryData>,
+    pub data: Option<GenericPrimaryData<R>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub included: Option<Resources>,
+    pub included: Option<Vec<GenericResource<R>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub links: Option<Links>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub meta: Option<Meta>,
+    pub meta: Option<M>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub jsonapi: Option<JsonApiInfo>,
 }
@@ -113,16 +191,28 @@ pub struct DocumentData {
 /// running validators on parsed documents
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
-pub enum JsonApiDocument {
-    Error(DocumentError),
-    Data(DocumentData),
+pub enum GenericJsonApiDocument<M, R = M> {
+    Error(GenericDocumentError<M>),
+    Data(GenericDocumentData<M, R>),
 }
 
-/// Error location
+/// A data document with [`Meta`](type.Meta.html) maps as its `meta`
+pub type DocumentData = GenericDocumentData<Meta>;
+/// A document with [`Meta`](type.Meta.html) maps as its `meta`
+pub type JsonApiDocument = GenericJsonApiDocument<Meta>;
+
+/// Error location: the member of the request document, the query parameter
+/// or the request header that caused the error
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct ErrorSource {
+    /// A JSON pointer ([RFC 6901](https://tools.ietf.org/html/rfc6901)) to
+    /// the value in the request document the error is about
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub pointer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub parameter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
 }
 
 /// Retpresentation of a JSON:API error (all fields are optional)
@@ -164,15 +254,27 @@ pub struct Pagination {
 }
 
 
-#[derive(Debug)]
+/// A change to one attribute or relationship of a resource. For
+/// relationships, `previous` and `next` are resource linkage: the `data` of
+/// the relationship.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Patch {
     pub patch_type: PatchType,
+    pub operation: PatchOperation,
+    /// The name of the attribute or relationship
     pub subject: String,
+    /// A JSON pointer into the value of the subject, empty when the patch
+    /// changes the whole value. A member removed from a to-many relationship
+    /// is at `/{index}`, in descending order of index, and a member added at
+    /// `/-`.
+    pub path: String,
+    /// The value before the change, `null` when it is added
     pub previous: JsonApiValue,
+    /// The value after the change, `null` when it is removed
     pub next: JsonApiValue,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PatchSet {
     pub resource_type: String,
     pub resource_id: String,
@@ -180,7 +282,7 @@ pub struct PatchSet {
 }
 
 impl PatchSet {
-    pub fn new_for(resource: &Resource) -> Self {
+    pub fn new_for<M>(resource: &GenericResource<M>) -> Self {
         PatchSet {
             resource_type: resource._type.clone(),
             resource_id: resource.id.clone(),
@@ -191,9 +293,466 @@ impl PatchSet {
     pub fn push(&mut self, patch: Patch) {
         self.patches.push(patch);
     }
+
+    /// Three-way merges two patch sets made from the same `base` resource:
+    /// the patches of `self`, then those of `theirs` that `self` does not
+    /// make as well. Patches of both that change the same value, or a value
+    /// and one it contains, conflict, as do patches of both inside an
+    /// attribute array one of them adds elements to or removes elements from,
+    /// since that moves the elements the other one refers to. Members added
+    /// to or removed from a to-many relationship never conflict with each
+    /// other. The conflicts are returned as `DiffPatchError::MergeConflicts`.
+    /// Patch sets made from another resource than `base` fail with
+    /// `IncompatibleTypes` or `IncompatibleIds`.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// let post = |title: &str, likes: u32| -> Resource {
+    ///     serde_json::from_value(serde_json::json!({
+    ///         "type": "posts", "id": "1", "attributes": { "title": title, "likes": likes }
+    ///     })).unwrap()
+    /// };
+    /// let base = post("Rails", 1);
+    /// let ours = base.diff(post("Rails is Omakase", 1)).unwrap();
+    /// let theirs = base.diff(post("Rails", 2)).unwrap();
+    ///
+    /// assert_eq!(base.merge(&ours, &theirs).unwrap(), post("Rails is Omakase", 2));
+    ///
+    /// let theirs = base.diff(post("Rails on Rust", 2)).unwrap();
+    /// match ours.merge(&theirs, &base) {
+    ///     Err(DiffPatchError::MergeConflicts(conflicts)) => {
+    ///         assert_eq!(conflicts.len(), 1);
+    ///         assert_eq!(conflicts[0].theirs.next, "Rails on Rust");
+    ///     }
+    ///     _ => panic!("expected a conflict"),
+    /// }
+    /// ```
+    pub fn merge<M>(&self, theirs: &PatchSet, base: &GenericResource<M>) -> std::result::Result<PatchSet, DiffPatchError> {
+        for patchset in &[self, theirs] {
+            if patchset.resource_type != base._type {
+                return Err(DiffPatchError::IncompatibleTypes(base._type.clone(), patchset.resource_type.clone()));
+            }
+            if patchset.resource_id != base.id {
+                return Err(DiffPatchError::IncompatibleIds(base.id.clone(), patchset.resource_id.clone()));
+            }
+        }
+
+        let mut merged = self.clone();
+        let mut conflicts = Vec::new();
+        for patch in &theirs.patches {
+            if self.patches.contains(patch) {
+                continue;
+            }
+            let overlapping: Vec<&Patch> = self.patches.iter().filter(|ours| overlap(base, ours, patch)).collect();
+            if overlapping.is_empty() {
+                merged.push(patch.clone());
+            }
+            for ours in overlapping {
+                conflicts.push(MergeConflict {
+                    ours: ours.clone(),
+                    theirs: patch.clone(),
+                });
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(merged)
+        } else {
+            Err(DiffPatchError::MergeConflicts(conflicts))
+        }
+    }
+}
+
+/// Whether the patches `a` and `b`, made from `base`, change the same value or
+/// a value inside the other, or move the elements of an array the other one
+/// changes an element of
+fn overlap<M>(base: &GenericResource<M>, a: &Patch, b: &Patch) -> bool {
+    if a.patch_type != b.patch_type || a.subject != b.subject {
+        return false;
+    }
+    // Members of to-many relationships are added and removed by identifier
+    if a.patch_type == PatchType::Relationship {
+        return a.path.is_empty() || b.path.is_empty();
+    }
+    let (a_tokens, b_tokens) = match (pointer::tokens(&a.path), pointer::tokens(&b.path)) {
+        (Ok(a_tokens), Ok(b_tokens)) => (a_tokens, b_tokens),
+        _ => return true,
+    };
+    let shared = a_tokens.len().min(b_tokens.len());
+    if a_tokens[..shared] == b_tokens[..shared] {
+        return true;
+    }
+    let moves_elements_of = |patch: &Patch, tokens: &[String], other: &[String]| {
+        if patch.operation == PatchOperation::Replace || tokens.is_empty() {
+            return false;
+        }
+        let array = &tokens[..tokens.len() - 1];
+        if other.len() <= array.len() || other[..array.len()] != *array {
+            return false;
+        }
+        let parent = &patch.path[..patch.path.rfind('/').unwrap_or(0)];
+        match base.attributes.get(&patch.subject).map(|value| pointer::resolve(value, parent)) {
+            Some(Ok(Some(value))) => value.is_array(),
+            _ => false,
+        }
+    };
+    moves_elements_of(a, &a_tokens, &b_tokens) || moves_elements_of(b, &b_tokens, &a_tokens)
+}
+
+/// Patches of both sides of a [three-way merge](struct.PatchSet.html#method.merge)
+/// that cannot both be applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    pub ours: Patch,
+    pub theirs: Patch,
+}
+
+/// An operation of a JSON Patch ([RFC 6902](https://tools.ietf.org/html/rfc6902))
+/// document
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum JsonPatchOperation {
+    Add { path: String, value: JsonApiValue },
+    Remove { path: String },
+    Replace { path: String, value: JsonApiValue },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: JsonApiValue },
+}
+
+impl PatchSet {
+    /// Exports the patch set as JSON Patch operations on the resource object
+    /// `base`: attributes are at `/attributes/{name}` and resource linkage at
+    /// `/relationships/{name}/data`. Every value removed or replaced is first
+    /// checked with a `test` operation holding its `previous` value. When
+    /// `base` has no relationships object, the first relationship added adds
+    /// one at `/relationships`.
+    ///
+    /// Patches only hold resource linkage, so relationships added are objects
+    /// with `data` alone: their `links` are not exported.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// let post = |title: &str| -> Resource {
+    ///     serde_json::from_value(serde_json::json!({ "type": "posts", "id": "1", "attributes": { "title": title } }))
+    ///         .unwrap()
+    /// };
+    /// let patchset = post("Rails").diff(post("Rails is Omakase")).unwrap();
+    ///
+    /// assert_eq!(
+    ///     serde_json::to_value(patchset.to_json_patch(&post("Rails"))).unwrap(),
+    ///     serde_json::json!([
+    ///         { "op": "test", "path": "/attributes/title", "value": "Rails" },
+    ///         { "op": "replace", "path": "/attributes/title", "value": "Rails is Omakase" }
+    ///     ])
+    /// );
+    /// ```
+    pub fn to_json_patch<M>(&self, base: &GenericResource<M>) -> Vec<JsonPatchOperation> {
+        let mut operations = Vec::new();
+        let mut has_relationships = base.relationships.is_some();
+        for patch in &self.patches {
+            let test = |path: &str| JsonPatchOperation::Test {
+                path: path.to_string(),
+                value: patch.previous.clone(),
+            };
+            let next = patch.next.clone();
+            match patch.patch_type {
+                PatchType::Attribute => {
+                    let path = format!("/attributes/{}{}", pointer::escape(&patch.subject), patch.path);
+                    match patch.operation {
+                        PatchOperation::Add => operations.push(JsonPatchOperation::Add { path, value: next }),
+                        PatchOperation::Remove => {
+                            operations.push(test(&path));
+                            operations.push(JsonPatchOperation::Remove { path });
+                        }
+                        PatchOperation::Replace => {
+                            operations.push(test(&path));
+                            operations.push(JsonPatchOperation::Replace { path, value: next });
+                        }
+                    }
+                }
+                PatchType::Relationship => {
+                    let relationship = format!("/relationships/{}", pointer::escape(&patch.subject));
+                    let path = format!("{}/data{}", relationship, patch.path);
+                    // Relationships without linkage have no `data` to test
+                    if !patch.previous.is_null() {
+                        operations.push(test(&path));
+                    }
+                    operations.push(match (patch.operation, patch.path.is_empty()) {
+                        (PatchOperation::Add, true) => {
+                            let mut members = serde_json::Map::new();
+                            members.insert("data".into(), next);
+                            if has_relationships {
+                                JsonPatchOperation::Add {
+                                    path: relationship,
+                                    value: JsonApiValue::Object(members),
+                                }
+                            } else {
+                                // `relationships` is left out of resource objects without any
+                                has_relationships = true;
+                                let mut relationships = serde_json::Map::new();
+                                relationships.insert(patch.subject.clone(), JsonApiValue::Object(members));
+                                JsonPatchOperation::Add {
+                                    path: "/relationships".into(),
+                                    value: JsonApiValue::Object(relationships),
+                                }
+                            }
+                        }
+                        (PatchOperation::Remove, true) => JsonPatchOperation::Remove { path: relationship },
+                        (PatchOperation::Replace, _) if patch.previous.is_null() => {
+                            JsonPatchOperation::Add { path, value: next }
+                        }
+                        (PatchOperation::Replace, _) => JsonPatchOperation::Replace { path, value: next },
+                        (PatchOperation::Add, false) => JsonPatchOperation::Add { path, value: next },
+                        (PatchOperation::Remove, false) => JsonPatchOperation::Remove { path },
+                    });
+                }
+            }
+        }
+        operations
+    }
+
+    /// Imports JSON Patch operations on the resource object `base`, reading
+    /// the `previous` value of each patch from `base` as the operations
+    /// before have changed it. `test` operations are checked rather than
+    /// imported, and `move` and `copy` are imported as the removals and
+    /// additions they make. Members added to to-many relationships are
+    /// appended, wherever the operation inserts them. Relationship objects
+    /// are imported as their `data`, leaving out their `links`, and the
+    /// relationships object can only be added when `base` has none.
+    ///
+    /// Operations on `type` or `id` fail with `ImmutableProperty`, and on
+    /// other members than attributes and resource linkage with
+    /// `UnsupportedPath`.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// let post: Resource = serde_json::from_value(serde_json::json!({
+    ///     "type": "posts", "id": "1", "attributes": { "title": "Rails" }
+    /// })).unwrap();
+    /// let operations: Vec<JsonPatchOperation> = serde_json::from_value(serde_json::json!([
+    ///     { "op": "test", "path": "/attributes/title", "value": "Rails" },
+    ///     { "op": "replace", "path": "/attributes/title", "value": "Rails is Omakase" }
+    /// ])).unwrap();
+    ///
+    /// let patchset = PatchSet::from_json_patch(&post, &operations).unwrap();
+    /// assert_eq!(patchset.patches[0].previous, "Rails");
+    ///
+    /// let operations = vec![JsonPatchOperation::Replace { path: "/id".into(), value: "2".into() }];
+    /// assert_eq!(
+    ///     PatchSet::from_json_patch(&post, &operations).unwrap_err(),
+    ///     DiffPatchError::ImmutableProperty("/id".into())
+    /// );
+    /// ```
+    pub fn from_json_patch<M: Clone>(
+        base: &GenericResource<M>,
+        operations: &[JsonPatchOperation],
+    ) -> std::result::Result<PatchSet, DiffPatchError> {
+        let mut patchset = PatchSet::new_for(base);
+        let mut current = base.clone();
+        for operation in operations {
+            import_operation(&mut current, &mut patchset, operation)?;
+        }
+        Ok(patchset)
+    }
+}
+
+/// What a JSON Patch path refers to in a resource object
+enum Location {
+    /// An attribute, and the path into its value
+    Attribute(String, String),
+    /// A relationship object
+    Relationship(String),
+    /// The linkage of a relationship, and the path into it
+    Linkage(String, String),
+}
+
+fn locate(path: &str) -> std::result::Result<Location, DiffPatchError> {
+    let unsupported = || DiffPatchError::UnsupportedPath(path.to_string());
+    let tokens = pointer::tokens(path).map_err(|_| unsupported())?;
+    let rest = |from: usize| -> String { tokens[from..].iter().map(|token| format!("/{}", pointer::escape(token))).collect() };
+    match tokens.first().map(String::as_str) {
+        Some("type") | Some("id") => Err(DiffPatchError::ImmutableProperty(path.to_string())),
+        Some("attributes") if tokens.len() > 1 => Ok(Location::Attribute(tokens[1].clone(), rest(2))),
+        Some("relationships") if tokens.len() == 2 => Ok(Location::Relationship(tokens[1].clone())),
+        Some("relationships") if (3..=4).contains(&tokens.len()) && tokens[2] == "data" => {
+            Ok(Location::Linkage(tokens[1].clone(), rest(3)))
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+/// The value at `location` in `resource`, `None` when there is none
+fn value_at<M>(resource: &GenericResource<M>, location: &Location) -> Option<JsonApiValue> {
+    let relationship = |name: &str| resource.relationships.as_ref().and_then(|relationships| relationships.get(name));
+    match *location {
+        Location::Attribute(ref subject, ref path) => resource
+            .attributes
+            .get(subject)
+            .and_then(|value| pointer::resolve(value, path).ok().flatten())
+            .cloned(),
+        Location::Relationship(ref subject) => relationship(subject).map(linkage_value),
+        Location::Linkage(ref subject, ref path) => {
+            let data = relationship(subject)?.data.as_ref().map_or(JsonApiValue::Null, linkage_value);
+            pointer::resolve(&data, path).ok().flatten().cloned()
+        }
+    }
+}
+
+/// Imports a JSON Patch operation into `patchset`, applying the patches it
+/// makes to `current`
+fn import_operation<M>(
+    current: &mut GenericResource<M>,
+    patchset: &mut PatchSet,
+    operation: &JsonPatchOperation,
+) -> std::result::Result<(), DiffPatchError> {
+    let (path, operation, value) = match *operation {
+        JsonPatchOperation::Test { ref path, ref value } => {
+            return if value_at(current, &locate(path)?).as_ref() == Some(value) {
+                Ok(())
+            } else {
+                Err(DiffPatchError::IncorrectPropertyValue(path.clone()))
+            };
+        }
+        JsonPatchOperation::Move { ref from, ref path } | JsonPatchOperation::Copy { ref from, ref path } => {
+            let value = value_at(current, &locate(from)?)
+                .ok_or_else(|| DiffPatchError::NonExistentProperty(from.clone()))?;
+            if let JsonPatchOperation::Move { .. } = *operation {
+                import_operation(current, patchset, &JsonPatchOperation::Remove { path: from.clone() })?;
+            }
+            return import_operation(current, patchset, &JsonPatchOperation::Add { path: path.clone(), value });
+        }
+        JsonPatchOperation::Add { ref path, ref value } if path == "/relationships" && current.relationships.is_none() => {
+            let relationships = value
+                .as_object()
+                .ok_or_else(|| DiffPatchError::IncorrectPropertyValue(path.clone()))?;
+            for (name, relationship) in relationships {
+                let path = format!("/relationships/{}", pointer::escape(name));
+                let value = relationship.clone();
+                import_operation(current, patchset, &JsonPatchOperation::Add { path, value })?;
+            }
+            return Ok(());
+        }
+        JsonPatchOperation::Add { ref path, ref value } => (path, PatchOperation::Add, value.clone()),
+        JsonPatchOperation::Remove { ref path } => (path, PatchOperation::Remove, JsonApiValue::Null),
+        JsonPatchOperation::Replace { ref path, ref value } => (path, PatchOperation::Replace, value.clone()),
+    };
+
+    let location = locate(path)?;
+    let previous = value_at(current, &location);
+    let non_existent = || DiffPatchError::NonExistentProperty(path.clone());
+    let patches = match location {
+        Location::Attribute(subject, attribute_path) => {
+            let into_array = match attribute_path.rfind('/') {
+                Some(at) => {
+                    let parent = Location::Attribute(subject.clone(), attribute_path[..at].to_string());
+                    value_at(current, &parent).is_some_and(|parent| parent.is_array())
+                }
+                None => false,
+            };
+            let patch = |operation, previous| {
+                new_patch(PatchType::Attribute, operation, &subject, attribute_path.clone(), previous, value.clone())
+            };
+            match (operation, previous) {
+                (PatchOperation::Add, _) if into_array => vec![patch(PatchOperation::Add, JsonApiValue::Null)],
+                (PatchOperation::Add, None) => vec![patch(PatchOperation::Add, JsonApiValue::Null)],
+                (PatchOperation::Remove, Some(previous)) => vec![patch(PatchOperation::Remove, previous)],
+                (_, Some(previous)) => vec![patch(PatchOperation::Replace, previous)],
+                (_, None) => return Err(non_existent()),
+            }
+        }
+        Location::Relationship(subject) => {
+            let linkage = value_at(current, &Location::Linkage(subject.clone(), String::new()));
+            let next = value.get("data").cloned().unwrap_or(JsonApiValue::Null);
+            let patch = |operation, previous, next| {
+                new_patch(PatchType::Relationship, operation, &subject, String::new(), previous, next)
+            };
+            match (operation, linkage) {
+                (PatchOperation::Add, None) => vec![patch(PatchOperation::Add, JsonApiValue::Null, next)],
+                (PatchOperation::Remove, Some(linkage)) => {
+                    vec![patch(PatchOperation::Remove, linkage, JsonApiValue::Null)]
+                }
+                (_, Some(linkage)) => vec![patch(PatchOperation::Replace, linkage, next)],
+                (_, None) => return Err(non_existent()),
+            }
+        }
+        Location::Linkage(subject, member_path) => {
+            let patch = |operation, path, previous, next| {
+                new_patch(PatchType::Relationship, operation, &subject, path, previous, next)
+            };
+            if member_path.is_empty() {
+                match (operation, previous) {
+                    (PatchOperation::Add, None) => vec![patch(PatchOperation::Add, String::new(), JsonApiValue::Null, value)],
+                    // Removing the linkage keeps the relationship and its links
+                    (PatchOperation::Remove, Some(linkage)) => {
+                        vec![patch(PatchOperation::Replace, String::new(), linkage, JsonApiValue::Null)]
+                    }
+                    (_, Some(linkage)) => vec![patch(PatchOperation::Replace, String::new(), linkage, value)],
+                    (_, None) => return Err(non_existent()),
+                }
+            } else {
+                match (operation, previous) {
+                    (PatchOperation::Add, _) => vec![patch(PatchOperation::Add, "/-".into(), JsonApiValue::Null, value)],
+                    (PatchOperation::Remove, Some(member)) => {
+                        vec![patch(PatchOperation::Remove, member_path, member, JsonApiValue::Null)]
+                    }
+                    (_, Some(member)) => vec![
+                        patch(PatchOperation::Remove, member_path, member, JsonApiValue::Null),
+                        patch(PatchOperation::Add, "/-".into(), JsonApiValue::Null, value),
+                    ],
+                    (_, None) => return Err(non_existent()),
+                }
+            }
+        }
+    };
+
+    for patch in patches {
+        current.apply(&patch)?;
+        patchset.push(patch);
+    }
+    Ok(())
+}
+
+impl<M> Default for GenericResource<M> {
+    fn default() -> Self {
+        GenericResource {
+            _type: String::new(),
+            id: JsonApiId::new(),
+            attributes: ResourceAttributes::new(),
+            relationships: None,
+            links: None,
+            meta: None,
+        }
+    }
+}
+
+impl<M> Default for GenericDocumentError<M> {
+    fn default() -> Self {
+        GenericDocumentError {
+            errors: JsonApiErrors::new(),
+            links: None,
+            meta: None,
+            jsonapi: None,
+        }
+    }
 }
 
-impl DocumentData {
+impl<M, R> Default for GenericDocumentData<M, R> {
+    fn default() -> Self {
+        GenericDocumentData {
+            data: None,
+            included: None,
+            links: None,
+            meta: None,
+            jsonapi: None,
+        }
+    }
+}
+
+impl<M, R> GenericDocumentData<M, R> {
     fn has_meta(&self) -> bool {
         self.meta.is_some()
     }
@@ -207,13 +766,15 @@ impl DocumentData {
 
 /// Top-level JSON-API Document
 /// An "error" document can be valid, just as a "data" document can be valid
-impl JsonApiDocument {
+impl<M, R> GenericJsonApiDocument<M, R> {
     /// This function returns `false` if the `JsonApiDocument` contains any violations of the
     /// specification. See [`DocumentValidationError`](enum.DocumentValidationError.html)
     ///
     /// The spec dictates that the document must have least one of `data`, `errors` or `meta`.
     /// Of these, `data` and `errors` must not co-exist.
     /// The optional field `included` may only be present if the `data` field is present too.
+    /// Error documents are checked with
+    /// [`DocumentError::validate`](struct.GenericDocumentError.html#method.validate).
     pub fn is_valid(&self) -> bool {
         self.validate().is_none()
     }
@@ -239,7 +800,7 @@ impl JsonApiDocument {
     ///
     /// let resource = Resource::from_str(&serialized);
     ///
-    /// let data = DocumentData {
+    /// let data: DocumentData = DocumentData {
     ///     data: None,
     ///     included: Some(vec![resource.unwrap()]),
     ///     ..Default::default()
@@ -262,8 +823,8 @@ impl JsonApiDocument {
         let mut errors = Vec::<DocumentValidationError>::new();
 
         match self {
-            JsonApiDocument::Error(_x) => None,
-            JsonApiDocument::Data(doc) => {
+            GenericJsonApiDocument::Error(doc) => doc.validate(),
+            GenericJsonApiDocument::Data(doc) => {
                 if doc.has_included() && !doc.has_data() {
                     errors.push(DocumentValidationError::IncludedWithoutData);
                 }
@@ -299,12 +860,113 @@ impl FromStr for JsonApiDocument {
     /// let doc = JsonApiDocument::from_str(&serialized);
     /// assert_eq!(doc.is_ok(), true);
     /// ```
+    ///
+    /// A document that does not match the specification fails with
+    /// `ParseError::InvalidMember`, which holds the JSON pointer to the
+    /// offending member
+    ///
+    /// ```
+    /// use jsonapi::api::JsonApiDocument;
+    /// use jsonapi::errors::{Error, ParseError};
+    /// use std::str::FromStr;
+    ///
+    /// let serialized = r#"{ "data": [{ "id": "1", "type": "post" }, { "id": 2, "type": "post" }] }"#;
+    /// let err = JsonApiDocument::from_str(&serialized).unwrap_err();
+    /// match err {
+    ///     Error::Parse(ParseError::InvalidMember(ref pointer, _)) => assert_eq!(pointer, "/data/1/id"),
+    ///     _ => panic!("unexpected error"),
+    /// }
+    /// ```
     fn from_str(s: &str) -> Result<Self> {
-        serde_json::from_str(s).chain_err(|| "Error parsing Document")
+        document_from_value(parse(s)?)
     }
 }
 
-impl Resource {
+/// The top-level members the specification defines
+const TOP_LEVEL_MEMBERS: [&str; 6] = ["data", "errors", "meta", "jsonapi", "links", "included"];
+
+/// Parses documents strictly: unlike `JsonApiDocument::from_str`, a document
+/// with both `data` and `errors` is rejected instead of read as an error
+/// document, and so are top-level members the specification does not define.
+/// Members of the extensions declared with
+/// [`extension`](#method.extension), named `namespace:member`, are accepted,
+/// as are `@`-members, which the specification reserves for implementations
+/// to ignore.
+///
+/// ```
+/// use jsonapi::api::*;
+/// use jsonapi::errors::{Error, ParseError};
+///
+/// let parser = StrictParser::new().extension("version");
+///
+/// let doc: JsonApiDocument = parser.parse(r#"{ "meta": {}, "version:id": "42" }"#).unwrap();
+/// assert!(doc.is_valid());
+///
+/// match parser.parse::<Meta>(r#"{ "data": null, "errors": [] }"#) {
+///     Err(Error::Parse(ParseError::Strict(err))) => assert_eq!(err, StrictDocumentError::DataAndErrors),
+///     _ => panic!("expected a strict document error"),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StrictParser {
+    /// Namespaces of the extensions whose members are accepted
+    pub extensions: Vec<String>,
+}
+
+impl StrictParser {
+    /// A parser accepting no extension members
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Declares the extension with the `namespace`, accepting its top-level
+    /// members
+    pub fn extension<S: Into<String>>(mut self, namespace: S) -> Self {
+        self.extensions.push(namespace.into());
+        self
+    }
+
+    /// Checks the top-level members of a parsed document against the rules
+    /// of strict parsing. Values that are not objects are left for
+    /// deserialization to reject.
+    pub fn check(&self, document: &JsonApiValue) -> std::result::Result<(), StrictDocumentError> {
+        let members = match *document {
+            JsonApiValue::Object(ref members) => members,
+            _ => return Ok(()),
+        };
+        if members.contains_key("data") && members.contains_key("errors") {
+            return Err(StrictDocumentError::DataAndErrors);
+        }
+        match members.keys().find(|name| !self.is_known(name)) {
+            Some(name) => Err(StrictDocumentError::UnknownMember(name.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn is_known(&self, name: &str) -> bool {
+        if TOP_LEVEL_MEMBERS.contains(&name) || name.starts_with('@') {
+            return true;
+        }
+        match name.find(':') {
+            Some(at) => self.extensions.iter().any(|namespace| *namespace == name[..at]),
+            None => false,
+        }
+    }
+
+    /// Parses a document, failing with `ParseError::Strict` when it breaks a
+    /// rule of strict parsing
+    pub fn parse<M: DeserializeOwned>(&self, s: &str) -> Result<GenericJsonApiDocument<M>> {
+        self.from_value(parse(s)?)
+    }
+
+    /// Like [`parse`](#method.parse), from a parsed value
+    pub fn from_value<M: DeserializeOwned>(&self, value: JsonApiValue) -> Result<GenericJsonApiDocument<M>> {
+        self.check(&value).map_err(ParseError::Strict)?;
+        document_from_value(value)
+    }
+}
+
+impl<M> GenericResource<M> {
     pub fn get_relationship(&self, name: &str) -> Option<&Relationship> {
         match self.relationships {
             None => None,
@@ -357,76 +1019,389 @@ impl Resource {
         }
     }
 
-    pub fn diff(&self, other: Resource) -> std::result::Result<PatchSet, DiffPatchError> {
+    /// The patches that turn `self` into `other`, changing its attributes and
+    /// its relationships. Attributes present on one side only are added or
+    /// removed, and those holding objects or arrays on both sides are diffed
+    /// member by member, with the `path` of the patches pointing into them.
+    /// Both resources must be of the same type.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// let post = |attributes: serde_json::Value| -> Resource {
+    ///     serde_json::from_value(serde_json::json!({ "type": "posts", "id": "1", "attributes": attributes })).unwrap()
+    /// };
+    /// let mut before = post(serde_json::json!({ "title": "Rails", "meta": { "tags": ["rails"] }, "draft": true }));
+    /// let after = post(serde_json::json!({ "title": "Rails", "meta": { "tags": ["rails", "news"] } }));
+    ///
+    /// let patchset = before.diff(after.clone()).unwrap();
+    /// assert_eq!(patchset.patches.len(), 2);
+    /// assert_eq!(patchset.patches[0].operation, PatchOperation::Remove);
+    /// assert_eq!(patchset.patches[0].subject, "draft");
+    /// assert_eq!(patchset.patches[1].operation, PatchOperation::Add);
+    /// assert_eq!(patchset.patches[1].path, "/tags/1");
+    ///
+    /// assert_eq!(before.patch(patchset).unwrap(), after);
+    /// ```
+    pub fn diff(&self, other: GenericResource<M>) -> std::result::Result<PatchSet, DiffPatchError> {
         if self._type != other._type {
-            Err(DiffPatchError::IncompatibleTypes(
-                self._type.clone(),
-                other._type.clone(),
-            ))
-        } else {
+            return Err(DiffPatchError::IncompatibleTypes(self._type.clone(), other._type.clone()));
+        }
 
-            let mut self_keys: Vec<String> =
-                self.attributes.iter().map(|(key, _)| key.clone()).collect();
+        let mut patchset = PatchSet::new_for(self);
+        self.diff_relationships(&other, &mut patchset);
 
-            self_keys.sort();
+        let mut names: Vec<&String> = self.attributes.keys().chain(other.attributes.keys()).collect();
+        names.sort();
+        names.dedup();
 
-            let mut other_keys: Vec<String> = other
-                .attributes
-                .iter()
-                .map(|(key, _)| key.clone())
-                .collect();
+        for name in names {
+            match (self.attributes.get(name), other.attributes.get(name)) {
+                (Some(previous), Some(next)) => diff_value(name, String::new(), previous, next, &mut patchset),
+                (Some(previous), None) => patchset.push(new_patch(
+                    PatchType::Attribute,
+                    PatchOperation::Remove,
+                    name,
+                    String::new(),
+                    previous.clone(),
+                    JsonApiValue::Null,
+                )),
+                (None, Some(next)) => patchset.push(new_patch(
+                    PatchType::Attribute,
+                    PatchOperation::Add,
+                    name,
+                    String::new(),
+                    JsonApiValue::Null,
+                    next.clone(),
+                )),
+                (None, None) => (),
+            }
+        }
 
-            other_keys.sort();
+        Ok(patchset)
+    }
 
-            let matching = self_keys
-                .iter()
-                .zip(other_keys.iter())
-                .filter(|&(a, b)| a == b)
-                .count();
+    /// Pushes the patches that turn the relationships of `self` into those of
+    /// `other`: relationships added, removed or replaced as a whole, and the
+    /// members added to or removed from to-many relationships
+    fn diff_relationships(&self, other: &GenericResource<M>, patchset: &mut PatchSet) {
+        let none = Relationships::new();
+        let own = self.relationships.as_ref().unwrap_or(&none);
+        let theirs = other.relationships.as_ref().unwrap_or(&none);
 
-            if matching != self_keys.len() {
-                Err(DiffPatchError::DifferentAttributeKeys)
-            } else {
-                let mut patchset = PatchSet::new_for(self);
-
-                for (attr, self_value) in &self.attributes {
-                    match other.attributes.get(attr) {
-                        None => {
-                            error!(
-                                "Resource::diff unable to find attribute {:?} in {:?}",
-                                attr,
-                                other
-                            );
+        let mut names: Vec<&String> = own.keys().chain(theirs.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        for name in names {
+            let previous = own.get(name).map(|rel| rel.data.as_ref());
+            let next = theirs.get(name).map(|rel| rel.data.as_ref());
+            match (previous, next) {
+                (Some(Some(IdentifierData::Multiple(previous))), Some(Some(IdentifierData::Multiple(next)))) => {
+                    for (index, identifier) in previous.iter().enumerate().rev() {
+                        if !next.contains(identifier) {
+                            patchset.push(new_patch(
+                                PatchType::Relationship,
+                                PatchOperation::Remove,
+                                name,
+                                format!("/{}", index),
+                                linkage_value(identifier),
+                                JsonApiValue::Null,
+                            ));
                         }
-                        Some(other_value) => {
-                            if self_value != other_value {
-                                patchset.push(Patch {
-                                    patch_type: PatchType::Attribute,
-                                    subject: attr.clone(),
-                                    previous: self_value.clone(),
-                                    next: other_value.clone(),
-                                });
-                            }
+                    }
+                    for identifier in next {
+                        if !previous.contains(identifier) {
+                            patchset.push(new_patch(
+                                PatchType::Relationship,
+                                PatchOperation::Add,
+                                name,
+                                "/-".into(),
+                                JsonApiValue::Null,
+                                linkage_value(identifier),
+                            ));
                         }
                     }
-
                 }
-
-                Ok(patchset)
+                (previous, next) if previous != next => {
+                    let operation = match (previous, next) {
+                        (None, _) => PatchOperation::Add,
+                        (_, None) => PatchOperation::Remove,
+                        _ => PatchOperation::Replace,
+                    };
+                    let previous = previous.and_then(|data| data).map_or(JsonApiValue::Null, linkage_value);
+                    let next = next.and_then(|data| data).map_or(JsonApiValue::Null, linkage_value);
+                    patchset.push(new_patch(PatchType::Relationship, operation, name, String::new(), previous, next));
+                }
+                _ => (),
             }
         }
     }
 
-    pub fn patch(&mut self, patchset: PatchSet) -> Result<Resource> {
+    /// Applies the patches of `patchset` to a copy of the resource. The
+    /// values a patch changes must still be its `previous` values, so a
+    /// resource changed since it was diffed is not silently overwritten; the
+    /// first patch that does not apply fails with `NonExistentProperty` or
+    /// `IncorrectPropertyValue`. See [`conflicts`](#method.conflicts) for all
+    /// of them.
+    pub fn patch(&mut self, patchset: PatchSet) -> Result<GenericResource<M>>
+    where
+        M: Clone,
+    {
         let mut res = self.clone();
         for patch in &patchset.patches {
-            res.attributes.insert(
-                patch.subject.clone(),
-                patch.next.clone(),
-            );
+            res.apply(patch)?;
         }
         Ok(res)
     }
+
+    /// The errors of all the patches of `patchset` that do not apply to the
+    /// resource, each naming the property of the patch. The patches are
+    /// applied in order, skipping those that do not apply.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// let post = |title: &str, likes: u32| -> Resource {
+    ///     serde_json::from_value(serde_json::json!({
+    ///         "type": "posts", "id": "1", "attributes": { "title": title, "likes": likes }
+    ///     })).unwrap()
+    /// };
+    /// let patchset = post("Rails", 1).diff(post("Rails is Omakase", 2)).unwrap();
+    ///
+    /// let current = post("Rails on Rust", 1);
+    /// assert_eq!(
+    ///     current.conflicts(&patchset),
+    ///     vec![DiffPatchError::IncorrectPropertyValue("title".into())]
+    /// );
+    /// ```
+    pub fn conflicts(&self, patchset: &PatchSet) -> Vec<DiffPatchError>
+    where
+        M: Clone,
+    {
+        let mut res = self.clone();
+        patchset.patches.iter().filter_map(|patch| res.apply(patch).err()).collect()
+    }
+
+    /// Merges two patch sets made from the resource, see
+    /// [`PatchSet::merge`](struct.PatchSet.html#method.merge), and applies
+    /// the result to a copy of it
+    pub fn merge(&self, ours: &PatchSet, theirs: &PatchSet) -> Result<GenericResource<M>>
+    where
+        M: Clone,
+    {
+        let merged = ours.merge(theirs, self)?;
+        self.clone().patch(merged)
+    }
+
+    fn apply(&mut self, patch: &Patch) -> std::result::Result<(), DiffPatchError> {
+        match patch.patch_type {
+            PatchType::Attribute => patch_attribute(&mut self.attributes, patch),
+            PatchType::Relationship => patch_relationship(&mut self.relationships, patch),
+        }
+    }
+}
+
+fn new_patch(
+    patch_type: PatchType,
+    operation: PatchOperation,
+    subject: &str,
+    path: String,
+    previous: JsonApiValue,
+    next: JsonApiValue,
+) -> Patch {
+    Patch {
+        patch_type,
+        operation,
+        subject: subject.to_string(),
+        path,
+        previous,
+        next,
+    }
+}
+
+/// Pushes the patches that turn the `previous` value at `path` in the
+/// attribute `subject` into the `next` one. Objects are diffed member by
+/// member; arrays element by element, with the elements only one of them has
+/// added in ascending or removed in descending order of index.
+fn diff_value(subject: &str, path: String, previous: &JsonApiValue, next: &JsonApiValue, patchset: &mut PatchSet) {
+    let patch = |operation, path, previous: &JsonApiValue, next: &JsonApiValue| {
+        new_patch(PatchType::Attribute, operation, subject, path, previous.clone(), next.clone())
+    };
+    match (previous, next) {
+        (JsonApiValue::Object(previous), JsonApiValue::Object(next)) => {
+            let mut keys: Vec<&String> = previous.keys().chain(next.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let path = format!("{}/{}", path, pointer::escape(key));
+                match (previous.get(key), next.get(key)) {
+                    (Some(previous), Some(next)) => diff_value(subject, path, previous, next, patchset),
+                    (Some(previous), None) => {
+                        patchset.push(patch(PatchOperation::Remove, path, previous, &JsonApiValue::Null))
+                    }
+                    (None, Some(next)) => patchset.push(patch(PatchOperation::Add, path, &JsonApiValue::Null, next)),
+                    (None, None) => (),
+                }
+            }
+        }
+        (JsonApiValue::Array(previous), JsonApiValue::Array(next)) => {
+            for (index, (previous, next)) in previous.iter().zip(next).enumerate() {
+                diff_value(subject, format!("{}/{}", path, index), previous, next, patchset);
+            }
+            for (index, previous) in previous.iter().enumerate().skip(next.len()).rev() {
+                patchset.push(patch(PatchOperation::Remove, format!("{}/{}", path, index), previous, &JsonApiValue::Null));
+            }
+            for (index, next) in next.iter().enumerate().skip(previous.len()) {
+                patchset.push(patch(PatchOperation::Add, format!("{}/{}", path, index), &JsonApiValue::Null, next));
+            }
+        }
+        (previous, next) if previous != next => patchset.push(patch(PatchOperation::Replace, path, previous, next)),
+        _ => (),
+    }
+}
+
+/// Applies an attribute patch. The value it removes or replaces must equal
+/// its `previous` value, and the object member it adds must not exist yet.
+/// Fails with `NonExistentProperty` when the value it changes, or the object
+/// or array it adds to, does not exist, and with `IncorrectPropertyValue`
+/// when the value found is not the one expected or its path does not lead
+/// into an object or array.
+fn patch_attribute(attributes: &mut ResourceAttributes, patch: &Patch) -> std::result::Result<(), DiffPatchError> {
+    let property = || format!("{}{}", patch.subject, patch.path);
+    let non_existent = || DiffPatchError::NonExistentProperty(property());
+    let incorrect = || DiffPatchError::IncorrectPropertyValue(property());
+    let expect_previous = |value: &JsonApiValue| if *value == patch.previous { Ok(()) } else { Err(incorrect()) };
+
+    let mut tokens = pointer::tokens(&patch.path).map_err(|_| incorrect())?;
+    let last = match tokens.pop() {
+        Some(last) => last,
+        None => {
+            match patch.operation {
+                PatchOperation::Add if attributes.contains_key(&patch.subject) => return Err(incorrect()),
+                PatchOperation::Add => {
+                    attributes.insert(patch.subject.clone(), patch.next.clone());
+                }
+                PatchOperation::Remove => {
+                    expect_previous(attributes.get(&patch.subject).ok_or_else(non_existent)?)?;
+                    attributes.remove(&patch.subject);
+                }
+                PatchOperation::Replace => {
+                    let value = attributes.get_mut(&patch.subject).ok_or_else(non_existent)?;
+                    expect_previous(value)?;
+                    *value = patch.next.clone();
+                }
+            }
+            return Ok(());
+        }
+    };
+
+    let mut parent = attributes.get_mut(&patch.subject).ok_or_else(non_existent)?;
+    for token in &tokens {
+        parent = pointer::child_mut(parent, token).ok_or_else(non_existent)?;
+    }
+    match *parent {
+        JsonApiValue::Object(ref mut members) => match patch.operation {
+            PatchOperation::Add if members.contains_key(&last) => return Err(incorrect()),
+            PatchOperation::Add => {
+                members.insert(last, patch.next.clone());
+            }
+            PatchOperation::Remove => {
+                expect_previous(members.get(&last).ok_or_else(non_existent)?)?;
+                members.remove(&last);
+            }
+            PatchOperation::Replace => {
+                let value = members.get_mut(&last).ok_or_else(non_existent)?;
+                expect_previous(value)?;
+                *value = patch.next.clone();
+            }
+        },
+        JsonApiValue::Array(ref mut elements) => {
+            let index = match (patch.operation, last.as_str()) {
+                (PatchOperation::Add, "-") => elements.len(),
+                _ => pointer::index(&last).ok_or_else(incorrect)?,
+            };
+            match patch.operation {
+                PatchOperation::Add if index <= elements.len() => elements.insert(index, patch.next.clone()),
+                PatchOperation::Remove if index < elements.len() => {
+                    expect_previous(&elements[index])?;
+                    elements.remove(index);
+                }
+                PatchOperation::Replace if index < elements.len() => {
+                    expect_previous(&elements[index])?;
+                    elements[index] = patch.next.clone();
+                }
+                _ => return Err(non_existent()),
+            }
+        }
+        _ => return Err(incorrect()),
+    }
+    Ok(())
+}
+
+/// The JSON of resource linkage
+fn linkage_value<T: serde::Serialize>(linkage: &T) -> JsonApiValue {
+    // Identifiers are made of strings, which always serialize
+    serde_json::to_value(linkage).unwrap_or(JsonApiValue::Null)
+}
+
+/// Applies a relationship patch, with the same checks as attribute patches.
+/// Members of to-many relationships are removed by identifier rather than
+/// index, and must not be added twice.
+fn patch_relationship(
+    relationships: &mut Option<Relationships>,
+    patch: &Patch,
+) -> std::result::Result<(), DiffPatchError> {
+    let property = || format!("{}{}", patch.subject, patch.path);
+    let non_existent = || DiffPatchError::NonExistentProperty(property());
+    let incorrect = || DiffPatchError::IncorrectPropertyValue(property());
+
+    let relationship = relationships.as_mut().and_then(|relationships| relationships.get_mut(&patch.subject));
+
+    if patch.path.is_empty() {
+        let data = Some(IdentifierData::deserialize(&patch.next).map_err(|_| incorrect())?);
+        match (patch.operation, relationship) {
+            (PatchOperation::Add, Some(_)) => return Err(incorrect()),
+            (PatchOperation::Add, None) => {
+                relationships
+                    .get_or_insert_with(Relationships::new)
+                    .insert(patch.subject.clone(), Relationship { data, links: None });
+            }
+            (_, None) => return Err(non_existent()),
+            (_, Some(ref relationship)) if relationship.data.as_ref().map_or(JsonApiValue::Null, linkage_value) != patch.previous => {
+                return Err(incorrect());
+            }
+            (PatchOperation::Remove, Some(_)) => {
+                if let Some(relationships) = relationships.as_mut() {
+                    relationships.remove(&patch.subject);
+                }
+            }
+            (PatchOperation::Replace, Some(relationship)) => relationship.data = data,
+        }
+        return Ok(());
+    }
+
+    let members = match relationship {
+        Some(Relationship { data: Some(IdentifierData::Multiple(members)), .. }) => members,
+        Some(_) => return Err(incorrect()),
+        None => return Err(non_existent()),
+    };
+    match patch.operation {
+        PatchOperation::Add => {
+            let identifier = ResourceIdentifier::deserialize(&patch.next).map_err(|_| incorrect())?;
+            if members.contains(&identifier) {
+                return Err(incorrect());
+            }
+            members.push(identifier);
+        }
+        PatchOperation::Remove => {
+            let identifier = ResourceIdentifier::deserialize(&patch.previous).map_err(|_| incorrect())?;
+            let index = members.iter().position(|member| *member == identifier).ok_or_else(non_existent)?;
+            members.remove(index);
+        }
+        PatchOperation::Replace => return Err(incorrect()),
+    }
+    Ok(())
 }
 
 impl FromStr for Resource {
@@ -453,7 +1428,37 @@ impl FromStr for Resource {
     /// assert_eq!(data.is_ok(), true);
     /// ```
     fn from_str(s: &str) -> Result<Self> {
-        serde_json::from_str(s).chain_err(|| "Error parsing resource")
+        parse(s)
+    }
+}
+
+fn invalid_member(err: serde_path_to_error::Error<serde_json::Error>) -> Error {
+    ParseError::InvalidMember(pointer::from_path(err.path()), err.into_inner()).into()
+}
+
+/// Deserializes JSON text, failing with the pointer to the member that does
+/// not match `T`
+fn parse<T: DeserializeOwned>(s: &str) -> Result<T> {
+    let mut deserializer = serde_json::Deserializer::from_str(s);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(invalid_member)?;
+    deserializer
+        .end()
+        .map_err(|err| ParseError::InvalidMember(String::new(), err))?;
+    Ok(value)
+}
+
+/// Like [`parse`](fn.parse.html), from a parsed value
+fn from_value<T: DeserializeOwned>(value: JsonApiValue) -> Result<T> {
+    serde_path_to_error::deserialize(value).map_err(invalid_member)
+}
+
+/// Reads an error document when `errors` is present, a data document
+/// otherwise
+fn document_from_value<M: DeserializeOwned>(value: JsonApiValue) -> Result<GenericJsonApiDocument<M>> {
+    if value.get("errors").is_some() {
+        from_value(value).map(GenericJsonApiDocument::Error)
+    } else {
+        from_value(value).map(GenericJsonApiDocument::Data)
     }
 }
 
@@ -468,7 +1473,7 @@ impl Relationship {
         }
     }
 
-    pub fn as_ids(&self) -> std::result::Result<Option<JsonApiIds>, RelationshipAssumptionError> {
+    pub fn as_ids(&self) -> std::result::Result<Option<JsonApiIds<'_>>, RelationshipAssumptionError> {
         match self.data {
             Some(IdentifierData::None) => Ok(None),
             Some(IdentifierData::Single(_)) => Err(RelationshipAssumptionError::RelationshipIsNotAList),
@@ -478,11 +1483,414 @@ impl Relationship {
     }
 }
 
+/// Builds error objects
+///
+/// ```
+/// use jsonapi::api::*;
+///
+/// let error = JsonApiError::new(422)
+///     .code("invalid")
+///     .detail("Email is not an email address")
+///     .pointer("/data/attributes/email");
+/// assert_eq!(error.status, Some("422".into()));
+/// assert_eq!(error.title, Some("Unprocessable Entity".into()));
+///
+/// let doc = DocumentError::from_errors(vec![error, JsonApiError::not_found()]);
+/// assert_eq!(doc.status(), Some(400));
+/// ```
+impl JsonApiError {
+    /// An error with the HTTP `status`, titled with the standard reason
+    /// phrase of the status when there is one
+    pub fn new(status: u16) -> Self {
+        JsonApiError {
+            status: Some(status.to_string()),
+            title: reason_phrase(status).map(String::from),
+            ..Default::default()
+        }
+    }
+
+    pub fn id<S: Into<String>>(mut self, id: S) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn code<S: Into<String>>(mut self, code: S) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn detail<S: Into<String>>(mut self, detail: S) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Sets the JSON pointer to the member of the request document the error
+    /// is about
+    pub fn pointer<S: Into<String>>(mut self, pointer: S) -> Self {
+        self.source.get_or_insert_with(ErrorSource::default).pointer = Some(pointer.into());
+        self
+    }
+
+    /// Sets the query parameter the error is about
+    pub fn parameter<S: Into<String>>(mut self, parameter: S) -> Self {
+        self.source.get_or_insert_with(ErrorSource::default).parameter = Some(parameter.into());
+        self
+    }
+
+    /// Sets the name of the request header the error is about
+    pub fn header<S: Into<String>>(mut self, header: S) -> Self {
+        self.source.get_or_insert_with(ErrorSource::default).header = Some(header.into());
+        self
+    }
+
+    pub fn link<N: Into<String>, H: Into<String>>(mut self, name: N, href: H) -> Self {
+        self.links
+            .get_or_insert_with(Links::new)
+            .insert(name.into(), JsonApiValue::String(href.into()));
+        self
+    }
+
+    pub fn meta<S: Into<String>, V: Into<JsonApiValue>>(mut self, name: S, value: V) -> Self {
+        self.meta.get_or_insert_with(Meta::new).insert(name.into(), value.into());
+        self
+    }
+
+    /// The `status` as a number, `None` when it is missing or not a number
+    pub fn status_code(&self) -> Option<u16> {
+        self.status.as_ref().and_then(|status| status.parse().ok())
+    }
+
+    /// Returns `true` when the error object has no
+    /// [`ErrorValidationError`](enum.ErrorValidationError.html)
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_none()
+    }
+
+    /// Checks that the `status`, if any, is a three digit HTTP status code of
+    /// a client or server error
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// assert!(JsonApiError::not_found().is_valid());
+    ///
+    /// let error = JsonApiError { status: Some("Not Found".into()), ..Default::default() };
+    /// assert_eq!(error.validate(), Some(vec![ErrorValidationError::InvalidStatus]));
+    ///
+    /// let error = JsonApiError::new(204);
+    /// assert_eq!(error.validate(), Some(vec![ErrorValidationError::NonErrorStatus]));
+    /// ```
+    pub fn validate(&self) -> Option<Vec<ErrorValidationError>> {
+        let status = self.status.as_ref()?;
+        let code = match status.parse::<u16>() {
+            Ok(code) if status.len() == 3 && status.bytes().all(|b| b.is_ascii_digit()) => code,
+            _ => return Some(vec![ErrorValidationError::InvalidStatus]),
+        };
+        match code {
+            400..=599 => None,
+            100..=399 => Some(vec![ErrorValidationError::NonErrorStatus]),
+            _ => Some(vec![ErrorValidationError::InvalidStatus]),
+        }
+    }
+}
+
+macro_rules! status_constructors {
+    ($($name:ident => $status:expr, $reason:expr);*) => {
+        impl JsonApiError {
+            $(
+                #[doc = concat!("A `", stringify!($status), " ", $reason, "` error")]
+                pub fn $name() -> Self {
+                    JsonApiError::new($status)
+                }
+            )*
+        }
+
+        fn reason_phrase(status: u16) -> Option<&'static str> {
+            match status {
+                $( $status => Some($reason), )*
+                _ => None,
+            }
+        }
+    };
+}
+
+status_constructors!(
+    bad_request => 400, "Bad Request";
+    unauthorized => 401, "Unauthorized";
+    forbidden => 403, "Forbidden";
+    not_found => 404, "Not Found";
+    method_not_allowed => 405, "Method Not Allowed";
+    not_acceptable => 406, "Not Acceptable";
+    conflict => 409, "Conflict";
+    gone => 410, "Gone";
+    precondition_failed => 412, "Precondition Failed";
+    unsupported_media_type => 415, "Unsupported Media Type";
+    unprocessable_entity => 422, "Unprocessable Entity";
+    too_many_requests => 429, "Too Many Requests";
+    internal_server_error => 500, "Internal Server Error";
+    not_implemented => 501, "Not Implemented";
+    service_unavailable => 503, "Service Unavailable"
+);
+
+impl DocumentError {
+    /// An error document holding `errors`. The status to respond with is
+    /// given by [`status`](#method.status).
+    pub fn from_errors<I>(errors: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<JsonApiError>,
+    {
+        DocumentError {
+            errors: errors.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl<M> GenericDocumentError<M> {
+    /// The most generally applicable HTTP status for the errors: their status
+    /// when they all share one, `400` when they are all client errors and
+    /// `500` otherwise. `None` when no error has a status.
+    pub fn status(&self) -> Option<u16> {
+        let mut statuses = self.errors.iter().filter_map(JsonApiError::status_code);
+        let first = statuses.next()?;
+        let (same, client) = statuses.fold((true, (400..500).contains(&first)), |(same, client), status| {
+            (same && status == first, client && (400..500).contains(&status))
+        });
+        Some(if same { first } else if client { 400 } else { 500 })
+    }
+
+    /// Returns `true` when the document has no
+    /// [`DocumentValidationError`](enum.DocumentValidationError.html)
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_none()
+    }
+
+    /// Returns the specification violations of the error document: an empty
+    /// `errors` array, error objects that are not valid (see
+    /// [`JsonApiError::validate`](struct.JsonApiError.html#method.validate)).
+    /// Statuses may differ from error to error; see
+    /// [`status`](#method.status) for the one to respond with, or
+    /// [`validate_statuses`](#method.validate_statuses) to also require them
+    /// to be consistent. A `status` that is not a string fails to parse in
+    /// the first place.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// let doc = DocumentError::from_errors(vec![JsonApiError::not_found(), JsonApiError::new(200)]);
+    /// assert_eq!(
+    ///     doc.validate(),
+    ///     Some(vec![DocumentValidationError::InvalidError(1, ErrorValidationError::NonErrorStatus)])
+    /// );
+    /// ```
+    pub fn validate(&self) -> Option<Vec<DocumentValidationError>> {
+        let mut errors = Vec::<DocumentValidationError>::new();
+
+        if self.errors.is_empty() {
+            errors.push(DocumentValidationError::MissingErrors);
+        }
+
+        for (index, error) in self.errors.iter().enumerate() {
+            for err in error.validate().into_iter().flatten() {
+                errors.push(DocumentValidationError::InvalidError(index, err));
+            }
+        }
+
+        match errors.len() {
+            0 => None,
+            _ => Some(errors),
+        }
+    }
+
+    /// Returns the violations of [`validate`](#method.validate), and
+    /// `InconsistentErrorStatuses` when the statuses of the valid error
+    /// objects mix client and server errors. The specification allows this,
+    /// but a server may opt into the check so that the response status it
+    /// sends applies to every error.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// let doc = DocumentError::from_errors(vec![JsonApiError::not_found(), JsonApiError::new(503)]);
+    /// assert_eq!(doc.validate(), None);
+    /// assert_eq!(doc.validate_statuses(), Some(vec![DocumentValidationError::InconsistentErrorStatuses]));
+    /// ```
+    pub fn validate_statuses(&self) -> Option<Vec<DocumentValidationError>> {
+        let mut errors = self.validate().unwrap_or_default();
+
+        let statuses = self.errors.iter().filter(|error| error.is_valid()).filter_map(JsonApiError::status_code);
+        let (client, server) = statuses.fold((false, false), |(client, server), status| {
+            (client || status < 500, server || status >= 500)
+        });
+        if client && server {
+            errors.push(DocumentValidationError::InconsistentErrorStatuses);
+        }
+
+        match errors.len() {
+            0 => None,
+            _ => Some(errors),
+        }
+    }
+}
+
+impl ErrorSource {
+    /// Checks that the `pointer`, if any, is a valid JSON pointer that
+    /// resolves to a value in the request `document`
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// let document = serde_json::json!({
+    ///     "data": { "type": "people", "attributes": { "first-name": "J" } }
+    /// });
+    /// let source = ErrorSource {
+    ///     pointer: Some("/data/attributes/first-name".into()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(source.validate_pointer(&document), Ok(()));
+    ///
+    /// let source = ErrorSource {
+    ///     pointer: Some("/data/attributes/last-name".into()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(source.validate_pointer(&document), Err(PointerValidationError::Unresolved));
+    /// ```
+    pub fn validate_pointer(&self, document: &JsonApiValue) -> std::result::Result<(), PointerValidationError> {
+        match self.pointer {
+            Some(ref pointer) => match pointer::resolve(document, pointer)? {
+                Some(_) => Ok(()),
+                None => Err(PointerValidationError::Unresolved),
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> From<&'a Error> for JsonApiError {
+    fn from(err: &'a Error) -> Self {
+        let error = match *err {
+            Error::Parse(ParseError::Json(_)) => JsonApiError::bad_request().code("invalid_json"),
+            Error::Parse(ParseError::InvalidMember(ref pointer, _)) => {
+                JsonApiError::bad_request().code("invalid_document").pointer(pointer.clone())
+            }
+            Error::Parse(ParseError::Strict(StrictDocumentError::DataAndErrors)) => {
+                JsonApiError::bad_request().code("data_and_errors").pointer("")
+            }
+            Error::Parse(ParseError::Strict(StrictDocumentError::UnknownMember(ref name))) => JsonApiError::bad_request()
+                .code("unknown_member")
+                .pointer(format!("/{}", pointer::escape(name))),
+            Error::Validation(err) => return err.into(),
+            Error::Conversion(ref err) => match *err {
+                ConversionError::NoData => JsonApiError::bad_request().code("no_data").pointer(""),
+                ConversionError::ResourceTypeMismatch(..) => JsonApiError::conflict().code("type_mismatch"),
+                ConversionError::InvalidResourceId(..) => JsonApiError::unprocessable_entity().code("invalid_id"),
+                ConversionError::UnknownAttribute(..) => {
+                    JsonApiError::unprocessable_entity().code("unknown_attribute")
+                }
+                ConversionError::MissingAttribute(..) => {
+                    JsonApiError::unprocessable_entity().code("missing_attribute")
+                }
+                ConversionError::UnresolvedRelationship(..) => {
+                    JsonApiError::unprocessable_entity().code("unresolved_relationship")
+                }
+                ConversionError::ModelSerialization(..) | ConversionError::AttributeSerialization(..) => {
+                    JsonApiError::internal_server_error()
+                }
+            },
+            Error::Patch(DiffPatchError::IncompatibleTypes(..)) => JsonApiError::conflict().code("type_mismatch"),
+            Error::Patch(DiffPatchError::IncompatibleIds(..)) => JsonApiError::conflict().code("id_mismatch"),
+            Error::Patch(DiffPatchError::MergeConflicts(_)) => JsonApiError::conflict().code("merge_conflict"),
+            Error::Patch(_) => JsonApiError::unprocessable_entity().code("invalid_patch"),
+            Error::Relationship(_) => JsonApiError::bad_request().code("invalid_relationship"),
+            Error::Data(_) => JsonApiError::bad_request().code("invalid_attribute"),
+            Error::Query(QueryError::Syntax(_)) => JsonApiError::bad_request().code("invalid_query"),
+            Error::Query(QueryError::InvalidPage(ref parameter, _)) => {
+                JsonApiError::bad_request().code("invalid_query").parameter(parameter.clone())
+            }
+        };
+        error.detail(err.to_string())
+    }
+}
+
+impl From<Error> for JsonApiError {
+    fn from(err: Error) -> Self {
+        JsonApiError::from(&err)
+    }
+}
+
+impl From<DocumentValidationError> for JsonApiError {
+    fn from(err: DocumentValidationError) -> Self {
+        match err {
+            DocumentValidationError::IncludedWithoutData => JsonApiError::bad_request()
+                .code("included_without_data")
+                .pointer("/included"),
+            DocumentValidationError::MissingContent => JsonApiError::bad_request()
+                .code("missing_content")
+                .pointer(""),
+            DocumentValidationError::MissingErrors => JsonApiError::bad_request()
+                .code("missing_errors")
+                .pointer("/errors"),
+            DocumentValidationError::InvalidError(index, _) => JsonApiError::bad_request()
+                .code("invalid_error_status")
+                .pointer(format!("/errors/{}/status", index)),
+            DocumentValidationError::InconsistentErrorStatuses => JsonApiError::bad_request()
+                .code("inconsistent_error_statuses")
+                .pointer("/errors"),
+        }
+        .detail(err.to_string())
+    }
+}
+
 /// Enum to describe top-level JSON:API specification violations
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum DocumentValidationError {
     IncludedWithoutData,
     MissingContent,
+    /// An error document whose `errors` array is empty
+    MissingErrors,
+    /// The error object at the index is not valid
+    InvalidError(usize, ErrorValidationError),
+    /// The statuses of the error objects mix client and server errors, see
+    /// [`DocumentError::validate_statuses`](struct.GenericDocumentError.html#method.validate_statuses)
+    InconsistentErrorStatuses,
+}
+
+/// Enum to describe why a [`JsonApiError`](struct.JsonApiError.html) is not
+/// valid
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum ErrorValidationError {
+    /// The `status` is not an HTTP status code
+    InvalidStatus,
+    /// The `status` is an HTTP status code, but not of a client or server
+    /// error
+    NonErrorStatus,
+}
+
+/// Enum to describe the rule of [strict parsing](struct.StrictParser.html) a
+/// document breaks
+#[derive(Debug, Clone, PartialEq)]
+pub enum StrictDocumentError {
+    /// The document has both `data` and `errors`
+    DataAndErrors,
+    /// A top-level member neither the specification nor a declared extension
+    /// defines
+    UnknownMember(String),
+}
+
+/// Enum to describe why the `pointer` of an
+/// [`ErrorSource`](struct.ErrorSource.html) is not valid
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum PointerValidationError {
+    /// The pointer is not an RFC 6901 JSON pointer
+    InvalidSyntax,
+    /// The pointer does not lead to a value in the document
+    Unresolved,
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -500,9 +1908,20 @@ pub enum RelationshipAssumptionError {
 #[derive(Debug, Clone, PartialEq)]
 pub enum DiffPatchError {
     IncompatibleTypes(String, String),
+    /// Patch sets made from resources with different ids cannot be merged
+    IncompatibleIds(String, String),
+    /// No longer returned: resources with different attributes are diffed
+    /// with patches adding and removing them
     DifferentAttributeKeys,
     NonExistentProperty(String),
     IncorrectPropertyValue(String),
+    /// Patches of both sides of a three-way merge change the same values
+    MergeConflicts(Vec<MergeConflict>),
+    /// A JSON Patch operation on the `type` or `id` of a resource
+    ImmutableProperty(String),
+    /// A JSON Patch operation on a member of a resource that is neither an
+    /// attribute nor resource linkage
+    UnsupportedPath(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -510,3 +1929,114 @@ pub enum PatchType {
     Relationship,
     Attribute,
 }
+
+/// What a [`Patch`](struct.Patch.html) does to its subject
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum PatchOperation {
+    Add,
+    Remove,
+    Replace,
+}
+
+impl fmt::Display for DocumentValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            DocumentValidationError::IncludedWithoutData => {
+                write!(f, "A document without data must not have included resources")
+            }
+            DocumentValidationError::MissingContent => write!(f, "A document must have data, errors or meta"),
+            DocumentValidationError::MissingErrors => write!(f, "An error document must have at least one error"),
+            DocumentValidationError::InvalidError(index, ref err) => write!(f, "Error {} is not valid: {}", index, err),
+            DocumentValidationError::InconsistentErrorStatuses => {
+                write!(f, "Errors must not mix client and server error statuses")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ErrorValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ErrorValidationError::InvalidStatus => write!(f, "Status is not an HTTP status code"),
+            ErrorValidationError::NonErrorStatus => write!(f, "Status is not a client or server error"),
+        }
+    }
+}
+
+impl fmt::Display for StrictDocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            StrictDocumentError::DataAndErrors => write!(f, "A document must not have both data and errors"),
+            StrictDocumentError::UnknownMember(ref name) => write!(f, "Unknown top-level member '{}'", name),
+        }
+    }
+}
+
+impl fmt::Display for PointerValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            PointerValidationError::InvalidSyntax => write!(f, "Pointer is not a JSON pointer"),
+            PointerValidationError::Unresolved => write!(f, "Pointer does not refer to a value in the document"),
+        }
+    }
+}
+
+impl fmt::Display for JsonApiDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            JsonApiDataError::AttributeNotFound => write!(f, "Attribute not found"),
+            JsonApiDataError::IncompatibleAttributeType => write!(f, "Attribute is of an incompatible type"),
+        }
+    }
+}
+
+impl fmt::Display for RelationshipAssumptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RelationshipAssumptionError::RelationshipIsAList => write!(f, "Relationship is a list"),
+            RelationshipAssumptionError::RelationshipIsNotAList => write!(f, "Relationship is not a list"),
+        }
+    }
+}
+
+impl fmt::Display for DiffPatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            DiffPatchError::IncompatibleTypes(ref a, ref b) => {
+                write!(f, "Resources of types '{}' and '{}' cannot be diffed", a, b)
+            }
+            DiffPatchError::IncompatibleIds(ref a, ref b) => {
+                write!(f, "Patches of resources with ids '{}' and '{}' cannot be merged", a, b)
+            }
+            DiffPatchError::DifferentAttributeKeys => write!(f, "Resources have different attributes"),
+            DiffPatchError::NonExistentProperty(ref property) => write!(f, "Property '{}' does not exist", property),
+            DiffPatchError::IncorrectPropertyValue(ref property) => {
+                write!(f, "Property '{}' does not have the expected value", property)
+            }
+            DiffPatchError::MergeConflicts(ref conflicts) => {
+                let properties: Vec<String> = conflicts
+                    .iter()
+                    .map(|conflict| format!("{}{}", conflict.theirs.subject, conflict.theirs.path))
+                    .collect();
+                write!(f, "Patches conflict on '{}'", properties.join("', '"))
+            }
+            DiffPatchError::ImmutableProperty(ref path) => write!(f, "Property '{}' cannot be changed", path),
+            DiffPatchError::UnsupportedPath(ref path) => write!(f, "Path '{}' cannot be patched", path),
+        }
+    }
+}
+
+impl std::error::Error for DocumentValidationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            DocumentValidationError::InvalidError(_, ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+impl std::error::Error for ErrorValidationError {}
+impl std::error::Error for StrictDocumentError {}
+impl std::error::Error for PointerValidationError {}
+impl std::error::Error for JsonApiDataError {}
+impl std::error::Error for RelationshipAssumptionError {}
+impl std::error::Error for DiffPatchError {}
@@ -1,27 +1,132 @@
-//! Defines trait and implementations that allow a `has many` relationship to be optional
-use crate::model::JsonApiModel;
+//! Defines trait and implementations that allow any collection of related
+//! values to be a `has many` relationship, and such a relationship to be
+//! optional.
+//!
+//! Implementations are provided for the std collections and `Box<[M]>`, for
+//! `smallvec::SmallVec` with the `smallvec` feature and for `indexmap::IndexSet`
+//! with the `indexmap` feature.
+use crate::related::JsonApiRelated;
+use std::collections::{BTreeSet, BinaryHeap, HashSet, LinkedList, VecDeque};
+use std::hash::BuildHasher;
+use std::iter::Flatten;
 
-/// Trait which allows a `has many` relationship to be optional.
+/// Trait for the collections a `has many` relationship can hold
 pub trait JsonApiArray<M> {
-    fn get_models(&self) -> &[M];
-    fn get_models_mut(&mut self) -> &mut [M];
+    /// The iterator over the related values
+    type Iter<'a>: Iterator<Item = &'a M>
+    where
+        Self: 'a,
+        M: 'a;
+
+    /// The related values, in the order they appear in the relationship
+    fn get_models(&self) -> Self::Iter<'_>;
+}
+
+/// Trait for the collections of a `has many` relationship whose values can be
+/// changed in place. Sets and heaps order or hash their values, so they only
+/// implement [`JsonApiArray`](trait.JsonApiArray.html).
+pub trait JsonApiArrayMut<M>: JsonApiArray<M> {
+    /// The iterator over mutable references to the related values
+    type IterMut<'a>: Iterator<Item = &'a mut M>
+    where
+        Self: 'a,
+        M: 'a;
+
+    /// The related values, in the order they appear in the relationship
+    fn get_models_mut(&mut self) -> Self::IterMut<'_>;
 }
 
-impl<M: JsonApiModel> JsonApiArray<M> for Vec<M> {
-    fn get_models(&self) -> &[M] { self }
-    fn get_models_mut(&mut self) -> &mut [M] { self }
+macro_rules! impl_array {
+    ($($collection:ty => $($module:ident)::+),*) => {
+        $(
+            impl<M: JsonApiRelated> JsonApiArray<M> for $collection {
+                type Iter<'a> = $($module)::+::Iter<'a, M> where Self: 'a, M: 'a;
+
+                fn get_models(&self) -> Self::Iter<'_> {
+                    self.iter()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_array_mut {
+    ($($collection:ty => $($module:ident)::+),*) => {
+        $(
+            impl<M: JsonApiRelated> JsonApiArrayMut<M> for $collection {
+                type IterMut<'a> = $($module)::+::IterMut<'a, M> where Self: 'a, M: 'a;
+
+                fn get_models_mut(&mut self) -> Self::IterMut<'_> {
+                    self.iter_mut()
+                }
+            }
+        )*
+    };
 }
 
-impl<M: JsonApiModel> JsonApiArray<M> for Option<Vec<M>> {
-    fn get_models(&self) -> &[M] {
-        self.as_ref()
-            .map(|v| v.as_slice())
-            .unwrap_or(&[][..])
+impl_array!(
+    Vec<M> => std::slice,
+    Box<[M]> => std::slice,
+    VecDeque<M> => std::collections::vec_deque,
+    LinkedList<M> => std::collections::linked_list,
+    BTreeSet<M> => std::collections::btree_set,
+    BinaryHeap<M> => std::collections::binary_heap
+);
+
+impl_array_mut!(
+    Vec<M> => std::slice,
+    Box<[M]> => std::slice,
+    VecDeque<M> => std::collections::vec_deque,
+    LinkedList<M> => std::collections::linked_list
+);
+
+impl<M: JsonApiRelated, S: BuildHasher> JsonApiArray<M> for HashSet<M, S> {
+    type Iter<'a> = std::collections::hash_set::Iter<'a, M> where Self: 'a, M: 'a;
+
+    fn get_models(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+}
+
+impl<M: JsonApiRelated, C: JsonApiArray<M>> JsonApiArray<M> for Option<C> {
+    type Iter<'a> = Flatten<std::option::IntoIter<C::Iter<'a>>> where Self: 'a, M: 'a;
+
+    fn get_models(&self) -> Self::Iter<'_> {
+        self.as_ref().map(C::get_models).into_iter().flatten()
     }
+}
+
+impl<M: JsonApiRelated, C: JsonApiArrayMut<M>> JsonApiArrayMut<M> for Option<C> {
+    type IterMut<'a> = Flatten<std::option::IntoIter<C::IterMut<'a>>> where Self: 'a, M: 'a;
+
+    fn get_models_mut(&mut self) -> Self::IterMut<'_> {
+        self.as_mut().map(C::get_models_mut).into_iter().flatten()
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<M: JsonApiRelated, A: smallvec::Array<Item = M>> JsonApiArray<M> for smallvec::SmallVec<A> {
+    type Iter<'a> = std::slice::Iter<'a, M> where Self: 'a, M: 'a;
+
+    fn get_models(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<M: JsonApiRelated, A: smallvec::Array<Item = M>> JsonApiArrayMut<M> for smallvec::SmallVec<A> {
+    type IterMut<'a> = std::slice::IterMut<'a, M> where Self: 'a, M: 'a;
+
+    fn get_models_mut(&mut self) -> Self::IterMut<'_> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<M: JsonApiRelated, S> JsonApiArray<M> for indexmap::IndexSet<M, S> {
+    type Iter<'a> = indexmap::set::Iter<'a, M> where Self: 'a, M: 'a;
 
-    fn get_models_mut(&mut self) -> &mut [M] {
-        self.as_mut()
-            .map(|v| v.as_mut_slice())
-            .unwrap_or(&mut [][..])
+    fn get_models(&self) -> Self::Iter<'_> {
+        self.iter()
     }
 }
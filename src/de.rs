@@ -0,0 +1,644 @@
+//! A `serde::Deserializer` that reads a model straight from a
+//! [`Resource`](../api/type.Resource.html) and the
+//! [`IncludedIndex`](../model/struct.IncludedIndex.html) of its document.
+//!
+//! The resource presents itself as a map of its `id`, its attributes and its
+//! relationships. Relationships are resolved through `included` lazily, as the
+//! model asks for them, so no intermediate `JsonApiValue` tree is built.
+//!
+//! Member names are mapped back to the names of the fields of the model using
+//! the naming policy of the model, or of the `ConversionContext`, which also
+//! decides how strictly resources are checked against the model.
+//!
+//! Every resource knows the JSON pointer to itself in its document, so values
+//! that do not fit the model fail with the pointer to the offending member.
+use crate::api::*;
+use crate::errors::{ConversionError, Error as JsonApiError, ParseError};
+use crate::model::{ConversionContext, IncludedIndex, ModelDescriptor};
+use crate::naming::NamingPolicy;
+use crate::pointer;
+use serde::de::value::{BorrowedStrDeserializer, MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use std::collections::hash_map;
+use std::fmt;
+
+/// The reasons a model can fail to deserialize from a resource
+#[derive(Debug)]
+pub(crate) enum Error {
+    /// A value could not be deserialized into a field of the model
+    Json(serde_json::Error),
+    /// The resource did not meet the requirements of the `ConversionContext`,
+    /// its `id` could not be converted, or a value that did not fit has been
+    /// located in the document
+    Conversion(JsonApiError),
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<ConversionError> for Error {
+    fn from(err: ConversionError) -> Self {
+        Error::Conversion(err.into())
+    }
+}
+
+impl From<Error> for JsonApiError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Json(err) => err.into(),
+            Error::Conversion(err) => err,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::Json(ref err) => err.fmt(f),
+            Error::Conversion(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Json(serde_json::Error::custom(msg))
+    }
+}
+
+/// Names a resource in errors
+fn describe(_type: &str, id: &str) -> String {
+    format!("{}/{}", _type, id)
+}
+
+/// Attributes a value that does not fit the model to the member at `pointer`.
+/// Errors that already know their member are passed on unchanged.
+fn locate(err: Error, pointer: String) -> Error {
+    match err {
+        Error::Json(err) => Error::Conversion(ParseError::InvalidMember(pointer, err).into()),
+        err => err,
+    }
+}
+
+/// Deserializes a model from a single resource. `path` holds the names of the
+/// relationships followed to reach this resource; a relationship that already
+/// appears in it is not followed again, which keeps documents where included
+/// resources refer back to their parents from recursing forever. `pointer`
+/// is the JSON pointer to the resource in its document.
+pub(crate) struct ResourceDeserializer<'de> {
+    resource: &'de Resource,
+    included: Option<&'de IncludedIndex<'de>>,
+    context: &'de ConversionContext,
+    descriptor: ModelDescriptor,
+    path: Vec<&'de str>,
+    pointer: String,
+}
+
+impl<'de> ResourceDeserializer<'de> {
+    pub(crate) fn new(
+        resource: &'de Resource,
+        included: Option<&'de IncludedIndex<'de>>,
+        context: &'de ConversionContext,
+        descriptor: ModelDescriptor,
+        pointer: String,
+    ) -> Self {
+        ResourceDeserializer {
+            resource,
+            included,
+            context,
+            descriptor,
+            path: Vec::new(),
+            pointer,
+        }
+    }
+
+    fn visit<V: Visitor<'de>>(self, visitor: V, fields: Option<&'static [&'static str]>) -> Result<V::Value, Error> {
+        let relationships = self.resource.relationships.as_ref().map(|relationships| relationships.iter());
+        let naming = self.descriptor.naming.unwrap_or(self.context.naming);
+        let names = FieldNames::new(naming, fields);
+        self.check_type()?;
+        let missing = match fields {
+            Some(fields) => self.check_attributes(fields, &names)?,
+            None => Vec::new(),
+        };
+        visitor
+            .visit_map(ResourceMapAccess {
+                resource: self.resource,
+                id: Some(&self.resource.id),
+                attributes: self.resource.attributes.iter(),
+                missing: missing.into_iter(),
+                relationships,
+                names,
+                included: self.included,
+                context: self.context,
+                descriptor: self.descriptor,
+                path: &self.path,
+                pointer: &self.pointer,
+                value: None,
+            })
+            .map_err(|err| locate(err, self.pointer.clone()))
+    }
+
+    /// Fails when the context checks types and the resource is not of the type
+    /// the model expects
+    fn check_type(&self) -> Result<(), Error> {
+        match self.descriptor.type_name {
+            Some(expected) if self.context.check_type && expected != self.resource._type => {
+                let resource = describe(&self.resource._type, &self.resource.id);
+                Err(ConversionError::ResourceTypeMismatch(resource, expected.to_string()).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies the attribute checks of the context to a resource deserialized
+    /// into a struct with the given `fields`. Returns the attribute fields that
+    /// are missing but may still deserialize, as `Option` fields do.
+    fn check_attributes(&self, fields: &'static [&'static str], names: &FieldNames)
+        -> Result<Vec<&'static str>, Error>
+    {
+        let is_attribute = |field: &str| field != "id" && (self.descriptor.relationship)(field).is_none();
+        if self.context.deny_unknown_attributes {
+            let unknown = self.resource.attributes.keys().find(|member| {
+                let field = names.field(member);
+                !fields.contains(&field) || !is_attribute(field)
+            });
+            if let Some(member) = unknown {
+                let resource = describe(&self.resource._type, &self.resource.id);
+                return Err(ConversionError::UnknownAttribute(resource, member.clone()).into());
+            }
+        }
+        if !self.context.deny_missing_attributes {
+            return Ok(Vec::new());
+        }
+        let missing: Vec<&'static str> = fields
+            .iter()
+            .cloned()
+            .filter(|&field| is_attribute(field) && !self.resource.attributes.contains_key(&names.member(field)))
+            .collect();
+        match missing.first() {
+            Some(&field) if !self.context.allow_missing_optional_attributes => {
+                let resource = describe(&self.resource._type, &self.resource.id);
+                Err(ConversionError::MissingAttribute(resource, names.member(field)).into())
+            }
+            _ => Ok(missing),
+        }
+    }
+}
+
+/// Maps member names back to the names of the fields they were serialized
+/// from. Only the fields serde asks for by name can be mapped, so models
+/// deserialized as maps see the member names unchanged.
+struct FieldNames {
+    naming: NamingPolicy,
+    renamed: Vec<(String, &'static str)>,
+}
+
+impl FieldNames {
+    fn new(naming: NamingPolicy, fields: Option<&'static [&'static str]>) -> Self {
+        let renamed = match (naming, fields) {
+            (NamingPolicy::AsIs, _) | (_, None) => Vec::new(),
+            (naming, Some(fields)) => fields
+                .iter()
+                .map(|&field| (naming.apply(field), field))
+                .filter(|(member, field)| member != field)
+                .collect(),
+        };
+        FieldNames { naming, renamed }
+    }
+
+    fn member(&self, field: &str) -> String {
+        self.naming.apply(field)
+    }
+
+    fn field<'de>(&self, member: &'de str) -> &'de str {
+        self.renamed
+            .iter()
+            .find(|(name, _)| name == member)
+            .map_or(member, |&(_, field)| field)
+    }
+}
+
+/// Deserializes a sequence of models from a list of resources and their
+/// pointers
+pub(crate) fn resources<'de, I>(
+    resources: I,
+    included: Option<&'de IncludedIndex<'de>>,
+    context: &'de ConversionContext,
+    descriptor: ModelDescriptor,
+) -> SeqDeserializer<impl Iterator<Item = ResourceDeserializer<'de>>, Error>
+where
+    I: IntoIterator<Item = (String, &'de Resource)>,
+{
+    SeqDeserializer::new(
+        resources
+            .into_iter()
+            .map(move |(pointer, resource)| ResourceDeserializer::new(resource, included, context, descriptor, pointer)),
+    )
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ResourceDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ResourceDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.visit(visitor, None)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.visit(visitor, Some(fields))
+    }
+
+    /// Enums are polymorphic relationships: the variant is chosen by the
+    /// `type` of the resource and deserialized from the resource itself
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match (self.descriptor.variant)(&self.resource._type) {
+            Some((variant, descriptor)) => visitor.visit_enum(VariantDeserializer {
+                variant,
+                resource: ResourceDeserializer { descriptor, ..self },
+            }),
+            None => Err(de::Error::custom(format_args!(
+                "resources of type '{}' cannot be deserialized into {}",
+                self.resource._type, name
+            ))),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map identifier ignored_any
+    }
+}
+
+/// The variant of a polymorphic relationship holding a resource
+struct VariantDeserializer<'de> {
+    variant: &'static str,
+    resource: ResourceDeserializer<'de>,
+}
+
+impl<'de> de::EnumAccess<'de> for VariantDeserializer<'de> {
+    type Error = Error;
+    type Variant = ResourceDeserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant), Error> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::<Error>::new(self.variant))?;
+        Ok((variant, self.resource))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for ResourceDeserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Err(de::Error::custom("polymorphic relationships must use newtype variants"))
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value, Error> {
+        Err(de::Error::custom("polymorphic relationships must use newtype variants"))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value, Error> {
+        Err(de::Error::custom("polymorphic relationships must use newtype variants"))
+    }
+}
+
+enum Entry<'de> {
+    Id(&'de str),
+    Attribute(&'de str, &'de JsonApiValue),
+    Missing(&'static str),
+    Relationship(&'de str, &'de Relationship),
+}
+
+struct ResourceMapAccess<'a, 'de: 'a> {
+    resource: &'de Resource,
+    id: Option<&'de str>,
+    attributes: hash_map::Iter<'de, String, JsonApiValue>,
+    missing: std::vec::IntoIter<&'static str>,
+    relationships: Option<hash_map::Iter<'de, String, Relationship>>,
+    names: FieldNames,
+    included: Option<&'de IncludedIndex<'de>>,
+    context: &'de ConversionContext,
+    descriptor: ModelDescriptor,
+    path: &'a [&'de str],
+    pointer: &'a str,
+    value: Option<Entry<'de>>,
+}
+
+impl<'a, 'de> MapAccess<'de> for ResourceMapAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        let (key, entry) = if let Some(id) = self.id.take() {
+            ("id", Entry::Id(id))
+        } else if let Some((key, value)) = self.attributes.next() {
+            (self.names.field(key), Entry::Attribute(key, value))
+        } else if let Some(field) = self.missing.next() {
+            (field, Entry::Missing(field))
+        } else {
+            let (path, descriptor, names) = (self.path, self.descriptor, &self.names);
+            let resolvable = self.included.is_some() || self.context.require_included;
+            let next = match self.relationships.as_mut() {
+                Some(relationships) => relationships.find(|&(name, _)| {
+                    !path.contains(&name.as_str())
+                        && (resolvable || relationship_descriptor(descriptor, names, name).linkage)
+                }),
+                None => None,
+            };
+            match next {
+                Some((name, relationship)) => (self.names.field(name), Entry::Relationship(name, relationship)),
+                None => return Ok(None),
+            }
+        };
+        self.value = Some(entry);
+        seed.deserialize(BorrowedStrDeserializer::new(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        match self.value.take() {
+            Some(Entry::Id(id)) => {
+                let value = (self.descriptor.id_value)(id).map_err(Error::Conversion)?;
+                seed.deserialize(value)
+                    .map_err(|err| locate(err.into(), format!("{}/id", self.pointer)))
+            }
+            Some(Entry::Attribute(member, value)) => {
+                let mut track = serde_path_to_error::Track::new();
+                seed.deserialize(serde_path_to_error::Deserializer::new(value, &mut track))
+                    .map_err(|err| {
+                        let mut pointer = format!("{}/attributes/{}", self.pointer, pointer::escape(member));
+                        pointer::push_path(&mut pointer, &track.path());
+                        locate(err.into(), pointer)
+                    })
+            }
+            Some(Entry::Missing(field)) => seed.deserialize(MissingDeserializer {
+                resource: self.resource,
+                member: self.names.member(field),
+            }),
+            Some(Entry::Relationship(name, relationship)) => {
+                let mut path = self.path.to_vec();
+                path.push(name);
+                seed.deserialize(RelationshipDeserializer {
+                    owner: self.resource,
+                    name,
+                    data: relationship.data.as_ref(),
+                    included: self.included,
+                    context: self.context,
+                    descriptor: relationship_descriptor(self.descriptor, &self.names, name),
+                    path,
+                })
+                .map_err(|err| locate(err, format!("{}/relationships/{}/data", self.pointer, pointer::escape(name))))
+            }
+            None => Err(de::Error::custom("next_value_seed called before next_key_seed")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.attributes.len() + self.missing.len() + self.id.map_or(0, |_| 1))
+    }
+}
+
+/// Stands in for an attribute missing from a resource: `Option` fields
+/// deserialize as `None`, any other field fails naming the attribute
+struct MissingDeserializer<'de> {
+    resource: &'de Resource,
+    member: String,
+}
+
+impl<'de> de::Deserializer<'de> for MissingDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+        let resource = describe(&self.resource._type, &self.resource.id);
+        Err(ConversionError::MissingAttribute(resource, self.member).into())
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_none()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// The descriptor of the relationship with the member name `name`
+fn relationship_descriptor(descriptor: ModelDescriptor, names: &FieldNames, name: &str) -> ModelDescriptor {
+    (descriptor.relationship)(names.field(name)).unwrap_or_default()
+}
+
+/// Deserializes the target(s) of the relationship `name` of `owner`.
+/// Identifiers that cannot be found in `included` deserialize as `null`, or
+/// fail when the context requires them to be included, unless the relationship
+/// holds linkage only, in which case the identifiers themselves are
+/// deserialized.
+struct RelationshipDeserializer<'de> {
+    owner: &'de Resource,
+    name: &'de str,
+    data: Option<&'de IdentifierData>,
+    included: Option<&'de IncludedIndex<'de>>,
+    context: &'de ConversionContext,
+    descriptor: ModelDescriptor,
+    path: Vec<&'de str>,
+}
+
+impl<'de> RelationshipDeserializer<'de> {
+    fn resolve(&self, identifier: &ResourceIdentifier) -> Result<Option<ResourceDeserializer<'de>>, Error> {
+        let resolved = self
+            .included
+            .and_then(|index| index.locate(identifier))
+            .map(|(position, resource)| ResourceDeserializer {
+                resource,
+                included: self.included,
+                context: self.context,
+                descriptor: self.descriptor,
+                path: self.path.clone(),
+                pointer: format!("/included/{}", position),
+            });
+        if resolved.is_none() && self.context.require_included {
+            return Err(ConversionError::UnresolvedRelationship(
+                describe(&self.owner._type, &self.owner.id),
+                self.name.to_string(),
+                describe(&identifier._type, &identifier.id),
+            )
+            .into());
+        }
+        Ok(resolved)
+    }
+
+    fn identifier(&self, identifier: &'de ResourceIdentifier) -> IdentifierDeserializer<'de> {
+        IdentifierDeserializer {
+            identifier,
+            descriptor: self.descriptor,
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for RelationshipDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.descriptor.linkage {
+            return match self.data {
+                Some(IdentifierData::Single(identifier)) => self.identifier(identifier).deserialize_any(visitor),
+                Some(IdentifierData::Multiple(identifiers)) => {
+                    let descriptor = self.descriptor;
+                    visitor.visit_seq(SeqDeserializer::new(
+                        identifiers
+                            .iter()
+                            .map(move |identifier| IdentifierDeserializer { identifier, descriptor }),
+                    ))
+                }
+                Some(IdentifierData::None) | None => visitor.visit_unit(),
+            };
+        }
+        match self.data {
+            Some(IdentifierData::Single(identifier)) => match self.resolve(identifier)? {
+                Some(resource) => resource.deserialize_any(visitor),
+                None => visitor.visit_unit(),
+            },
+            Some(IdentifierData::Multiple(identifiers)) => visitor.visit_seq(RelatedSeqAccess {
+                identifiers: identifiers.iter(),
+                relationship: &self,
+            }),
+            Some(IdentifierData::None) | None => visitor.visit_unit(),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.data {
+            Some(IdentifierData::Single(identifier)) if self.descriptor.linkage => {
+                visitor.visit_some(self.identifier(identifier))
+            }
+            Some(IdentifierData::Single(identifier)) => match self.resolve(identifier)? {
+                Some(resource) => visitor.visit_some(resource),
+                None => visitor.visit_none(),
+            },
+            Some(IdentifierData::Multiple(_)) => visitor.visit_some(self),
+            Some(IdentifierData::None) | None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.data {
+            Some(IdentifierData::Single(identifier)) if !self.descriptor.linkage => match self.resolve(identifier)? {
+                Some(resource) => resource.deserialize_struct(name, fields, visitor),
+                None => visitor.visit_unit(),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.data {
+            Some(IdentifierData::Single(identifier)) if !self.descriptor.linkage => match self.resolve(identifier)? {
+                Some(resource) => resource.deserialize_enum(name, variants, visitor),
+                None => visitor.visit_unit(),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map identifier ignored_any
+    }
+}
+
+struct RelatedSeqAccess<'a, 'de: 'a> {
+    identifiers: std::slice::Iter<'de, ResourceIdentifier>,
+    relationship: &'a RelationshipDeserializer<'de>,
+}
+
+impl<'a, 'de> SeqAccess<'de> for RelatedSeqAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+        match self.identifiers.next() {
+            Some(identifier) => match self.relationship.resolve(identifier)? {
+                Some(resource) => seed.deserialize(resource).map(Some),
+                None => seed.deserialize(().into_deserializer()).map(Some),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.identifiers.len())
+    }
+}
+
+/// Deserializes the linkage of a relationship as a map of its `type` and its
+/// `id`, the latter converted like the `id` of a resource
+struct IdentifierDeserializer<'de> {
+    identifier: &'de ResourceIdentifier,
+    descriptor: ModelDescriptor,
+}
+
+impl<'de> IntoDeserializer<'de, Error> for IdentifierDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for IdentifierDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let id = (self.descriptor.id_value)(&self.identifier.id).map_err(Error::Conversion)?;
+        visitor.visit_map(MapDeserializer::new(
+            vec![("type", JsonApiValue::String(self.identifier._type.clone())), ("id", id)].into_iter(),
+        ))
+        .map_err(Error::from)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
@@ -0,0 +1,220 @@
+//! Defines `Document` and `CollectionDocument`, typed JSON:API documents whose
+//! primary data is one model or a collection of models.
+//!
+//! Both implement `Serialize` and `Deserialize` with the wire format of a
+//! JSON:API document, so they can be used directly as request and response
+//! bodies with any serde based framework. The models are converted with the
+//! default [`ConversionContext`](../model/struct.ConversionContext.html); use
+//! the `_with` methods to convert with another one.
+//!
+//! The top-level `meta` is a [`Meta`](../api/type.Meta.html) map, or of
+//! another type with `GenericDocument` and `GenericCollectionDocument`:
+//!
+//! ```rust
+//! #[macro_use] extern crate serde_derive;
+//! #[macro_use] extern crate jsonapi;
+//! extern crate serde_json;
+//! use jsonapi::document::*;
+//! use jsonapi::model::*;
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Flea {
+//!     id: String,
+//!     name: String,
+//! }
+//! jsonapi_model!(Flea; "flea");
+//!
+//! let body = r#"{"data": {"type": "flea", "id": "1", "attributes": {"name": "rick"}}}"#;
+//! let doc: Document<Flea> = serde_json::from_str(body).unwrap();
+//! assert_eq!(doc.data.name, "rick");
+//!
+//! let json = serde_json::to_value(&doc).unwrap();
+//! assert_eq!(json["data"]["attributes"]["name"], "rick");
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct PageMeta {
+//!     total: u64,
+//! }
+//!
+//! let body = r#"{
+//!     "data": [{"type": "flea", "id": "1", "attributes": {"name": "rick"}}],
+//!     "meta": {"total": 12}
+//! }"#;
+//! let doc: GenericCollectionDocument<Flea, PageMeta> = serde_json::from_str(body).unwrap();
+//! assert_eq!(doc.meta, Some(PageMeta { total: 12 }));
+//! ```
+use crate::api::*;
+use crate::errors::*;
+use crate::model::{vec_from_jsonapi_document_with, vec_to_jsonapi_resources_with, ConversionContext, JsonApiModel};
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{self, Serialize, Serializer};
+
+/// A JSON:API document whose primary data is a single model, with a
+/// top-level `meta` of type `M`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenericDocument<T, M> {
+    pub data: T,
+    pub links: Option<Links>,
+    pub meta: Option<M>,
+    pub jsonapi: Option<JsonApiInfo>,
+}
+
+/// A document whose primary data is a single model, with a
+/// [`Meta`](../api/type.Meta.html) map as its `meta`
+pub type Document<T> = GenericDocument<T, Meta>;
+
+/// A JSON:API document whose primary data is a collection of models, with a
+/// top-level `meta` of type `M`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenericCollectionDocument<T, M> {
+    pub data: Vec<T>,
+    pub links: Option<Links>,
+    pub meta: Option<M>,
+    pub jsonapi: Option<JsonApiInfo>,
+}
+
+/// A document whose primary data is a collection of models, with a
+/// [`Meta`](../api/type.Meta.html) map as its `meta`
+pub type CollectionDocument<T> = GenericCollectionDocument<T, Meta>;
+
+impl<T, M> GenericDocument<T, M> {
+    /// A document holding `data` and no top-level `links`, `meta` or `jsonapi`
+    pub fn new(data: T) -> Self {
+        GenericDocument {
+            data,
+            links: None,
+            meta: None,
+            jsonapi: None,
+        }
+    }
+}
+
+impl<T, M> From<T> for GenericDocument<T, M> {
+    fn from(data: T) -> Self {
+        GenericDocument::new(data)
+    }
+}
+
+impl<T: JsonApiModel, M> GenericDocument<T, M> {
+    /// Converts a parsed document, with the model converted according to
+    /// `ctx`
+    pub fn from_document_data_with(doc: GenericDocumentData<M, Meta>, ctx: &ConversionContext) -> Result<Self> {
+        Ok(GenericDocument {
+            data: T::from_jsonapi_document_with(&doc, ctx)?,
+            links: doc.links,
+            meta: doc.meta,
+            jsonapi: doc.jsonapi,
+        })
+    }
+
+    /// Converts the document into a
+    /// [`GenericDocumentData`](../api/struct.GenericDocumentData.html), with
+    /// the model converted according to `ctx`
+    pub fn to_document_data_with(&self, ctx: &ConversionContext) -> Result<GenericDocumentData<M, Meta>>
+    where
+        M: Clone,
+    {
+        self.document_data(ctx, self.meta.clone())
+    }
+
+    fn document_data<N>(&self, ctx: &ConversionContext, meta: Option<N>) -> Result<GenericDocumentData<N, Meta>> {
+        let (resource, included) = self.data.try_to_jsonapi_resource_with(ctx)?;
+        Ok(GenericDocumentData {
+            data: Some(PrimaryData::Single(Box::new(resource))),
+            included,
+            links: self.links.clone(),
+            meta,
+            jsonapi: self.jsonapi.clone(),
+        })
+    }
+}
+
+impl<T, M> GenericCollectionDocument<T, M> {
+    /// A document holding `data` and no top-level `links`, `meta` or `jsonapi`
+    pub fn new(data: Vec<T>) -> Self {
+        GenericCollectionDocument {
+            data,
+            links: None,
+            meta: None,
+            jsonapi: None,
+        }
+    }
+}
+
+impl<T, M> From<Vec<T>> for GenericCollectionDocument<T, M> {
+    fn from(data: Vec<T>) -> Self {
+        GenericCollectionDocument::new(data)
+    }
+}
+
+impl<T: JsonApiModel, M> GenericCollectionDocument<T, M> {
+    /// Converts a parsed document, with the models converted according to
+    /// `ctx`. A document with a single primary resource results in a
+    /// collection of one.
+    pub fn from_document_data_with(doc: GenericDocumentData<M, Meta>, ctx: &ConversionContext) -> Result<Self> {
+        Ok(GenericCollectionDocument {
+            data: vec_from_jsonapi_document_with(&doc, ctx)?,
+            links: doc.links,
+            meta: doc.meta,
+            jsonapi: doc.jsonapi,
+        })
+    }
+
+    /// Converts the document into a
+    /// [`GenericDocumentData`](../api/struct.GenericDocumentData.html), with
+    /// the models converted according to `ctx`
+    pub fn to_document_data_with(&self, ctx: &ConversionContext) -> Result<GenericDocumentData<M, Meta>>
+    where
+        M: Clone,
+    {
+        self.document_data(ctx, self.meta.clone())
+    }
+
+    fn document_data<N>(&self, ctx: &ConversionContext, meta: Option<N>) -> Result<GenericDocumentData<N, Meta>> {
+        let (resources, included) = vec_to_jsonapi_resources_with(&self.data, ctx)?;
+        Ok(GenericDocumentData {
+            data: Some(PrimaryData::Multiple(resources)),
+            included,
+            links: self.links.clone(),
+            meta,
+            jsonapi: self.jsonapi.clone(),
+        })
+    }
+}
+
+/// The body the documents deserialize from: a data document, with the
+/// `errors` of an error document noted so that it is rejected rather than
+/// read as a document without data
+#[derive(Deserialize)]
+struct DocumentBody<M> {
+    #[serde(flatten)]
+    doc: GenericDocumentData<M, Meta>,
+    errors: Option<de::IgnoredAny>,
+}
+
+macro_rules! impl_serde {
+    ($($document:ident),*) => {
+        $(
+            impl<T: JsonApiModel, M: Serialize> Serialize for $document<T, M> {
+                fn serialize<S: Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+                    self.document_data(&ConversionContext::default(), self.meta.as_ref())
+                        .map_err(ser::Error::custom)?
+                        .serialize(serializer)
+                }
+            }
+
+            impl<'de, T: JsonApiModel, M: Deserialize<'de>> Deserialize<'de> for $document<T, M> {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+                    let body = DocumentBody::<M>::deserialize(deserializer)?;
+                    if body.errors.is_some() {
+                        return Err(de::Error::custom("expected a data document, found an error document"));
+                    }
+                    $document::from_document_data_with(body.doc, &ConversionContext::default())
+                        .map_err(de::Error::custom)
+                }
+            }
+        )*
+    };
+}
+
+impl_serde!(GenericDocument, GenericCollectionDocument);
@@ -1,11 +1,229 @@
-error_chain!{
-    foreign_links {
-        SerdeJson(serde_json::Error);
+//! Defines the errors of the crate. [`Error`](enum.Error.html) groups them by
+//! what failed: parsing a document, validating it against the specification,
+//! converting between models and resources, computing or applying patches, or
+//! reading query parameters. Every error implements `std::error::Error`, and
+//! converts into a [`JsonApiError`](../api/struct.JsonApiError.html) to respond
+//! with.
+//!
+//! ```rust
+//! use jsonapi::api::JsonApiDocument;
+//! use jsonapi::errors::*;
+//! use std::str::FromStr;
+//!
+//! match JsonApiDocument::from_str(r#"{ "data": { "type": "posts", "id": 1 } }"#) {
+//!     Err(Error::Parse(ParseError::InvalidMember(ref pointer, _))) => assert_eq!(pointer, "/data/id"),
+//!     _ => panic!("expected an invalid member"),
+//! }
+//! ```
+use crate::api::{
+    DiffPatchError, DocumentValidationError, JsonApiDataError, RelationshipAssumptionError, StrictDocumentError,
+};
+use std::error::Error as StdError;
+use std::fmt;
+
+/// Result type of the fallible operations of the crate
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The errors of the crate
+#[derive(Debug)]
+pub enum Error {
+    /// JSON that could not be parsed, or that does not have the structure
+    /// expected of it
+    Parse(ParseError),
+    /// A document that violates the specification
+    Validation(DocumentValidationError),
+    /// A model that could not be converted to or from a resource
+    Conversion(ConversionError),
+    /// Resources that could not be diffed or patched
+    Patch(DiffPatchError),
+    /// A relationship that does not hold the number of identifiers assumed
+    Relationship(RelationshipAssumptionError),
+    /// An attribute that is missing or of another type than assumed
+    Data(JsonApiDataError),
+    /// Query parameters that could not be read
+    Query(QueryError),
+}
+
+/// The reasons JSON can fail to parse
+#[derive(Debug)]
+pub enum ParseError {
+    /// Text that is not JSON, or a value that does not fit the type it is
+    /// deserialized into
+    Json(serde_json::Error),
+    /// A member of a document that does not fit the type it is deserialized
+    /// into, with the JSON pointer to the member
+    InvalidMember(String, serde_json::Error),
+    /// A document breaking a rule of
+    /// [strict parsing](../api/struct.StrictParser.html)
+    Strict(StrictDocumentError),
+}
+
+/// The reasons a model can fail to convert to or from a resource. Resources
+/// are named as `type/id`.
+#[derive(Debug)]
+pub enum ConversionError {
+    /// A document without primary data
+    NoData,
+    /// An `id` that cannot be converted into the id type of the model, and
+    /// the reason why
+    InvalidResourceId(String, String),
+    /// A resource whose type is not the type of the model, and the expected
+    /// type
+    ResourceTypeMismatch(String, String),
+    /// A resource with an attribute the model does not know
+    UnknownAttribute(String, String),
+    /// A resource missing an attribute of the model
+    MissingAttribute(String, String),
+    /// A relationship of a resource referring to a resource that is not
+    /// included: the resource, the relationship and the related resource
+    UnresolvedRelationship(String, String, String),
+    /// A model that does not serialize into a resource, and the reason why
+    ModelSerialization(String, Box<dyn StdError + Send + Sync>),
+    /// A field of a model that does not serialize into an attribute: the
+    /// model, the field and the reason why
+    AttributeSerialization(String, String, serde_json::Error),
+}
+
+/// The reasons query parameters can fail to be read
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryError {
+    /// A query string that cannot be parsed
+    Syntax(String),
+    /// A `page` parameter that is not an integer, and its value
+    InvalidPage(String, String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::Parse(ref err) => err.fmt(f),
+            Error::Validation(ref err) => err.fmt(f),
+            Error::Conversion(ref err) => err.fmt(f),
+            Error::Patch(ref err) => err.fmt(f),
+            Error::Relationship(ref err) => err.fmt(f),
+            Error::Data(ref err) => err.fmt(f),
+            Error::Query(ref err) => err.fmt(f),
+        }
+    }
+}
+
+/// `Error` displays as the error it wraps, so the source is the source of
+/// that error rather than the error itself
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            Error::Parse(ref err) => err.source(),
+            Error::Validation(ref err) => err.source(),
+            Error::Conversion(ref err) => err.source(),
+            Error::Patch(ref err) => err.source(),
+            Error::Relationship(ref err) => err.source(),
+            Error::Data(ref err) => err.source(),
+            Error::Query(ref err) => err.source(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParseError::Json(ref err) => write!(f, "Error parsing JSON: {}", err),
+            ParseError::InvalidMember(ref pointer, ref err) => {
+                write!(f, "Invalid document member '{}': {}", pointer, err)
+            }
+            ParseError::Strict(ref err) => write!(f, "Invalid document: {}", err),
+        }
+    }
+}
+
+impl StdError for ParseError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            ParseError::Json(ref err) | ParseError::InvalidMember(_, ref err) => Some(err),
+            ParseError::Strict(ref err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ConversionError::NoData => write!(f, "Document had no data"),
+            ConversionError::InvalidResourceId(ref id, ref reason) => {
+                write!(f, "Invalid Resource id '{}': {}", id, reason)
+            }
+            ConversionError::ResourceTypeMismatch(ref resource, ref expected) => {
+                write!(f, "Resource '{}' is not of type '{}'", resource, expected)
+            }
+            ConversionError::UnknownAttribute(ref resource, ref attribute) => {
+                write!(f, "Resource '{}' has unknown attribute '{}'", resource, attribute)
+            }
+            ConversionError::MissingAttribute(ref resource, ref attribute) => {
+                write!(f, "Resource '{}' is missing attribute '{}'", resource, attribute)
+            }
+            ConversionError::UnresolvedRelationship(ref resource, ref relationship, ref related) => write!(
+                f,
+                "Relationship '{}' of Resource '{}' refers to '{}', which is not included",
+                relationship, resource, related
+            ),
+            ConversionError::ModelSerialization(ref model, ref reason) => {
+                write!(f, "Error converting Model '{}' to Resource: {}", model, reason)
+            }
+            ConversionError::AttributeSerialization(ref model, ref field, ref reason) => write!(
+                f,
+                "Error converting attribute '{}' of Model '{}' to Resource: {}",
+                field, model, reason
+            ),
+        }
+    }
+}
+
+impl StdError for ConversionError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            ConversionError::ModelSerialization(_, ref err) => Some(&**err),
+            ConversionError::AttributeSerialization(_, _, ref err) => Some(err),
+            _ => None,
+        }
     }
-    errors {
-        ResourceToModelError(t: String) {
-            description("Error converting Resource to Model")
-            display("Error converting Resource to Model: '{}'", t)
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            QueryError::Syntax(ref reason) => write!(f, "Error parsing query parameters: {}", reason),
+            QueryError::InvalidPage(ref parameter, ref value) => {
+                write!(f, "Query parameter '{}' is not an integer: '{}'", parameter, value)
+            }
         }
     }
 }
+
+impl StdError for QueryError {}
+
+macro_rules! impl_from {
+    ($($variant:ident($err:ty)),*) => {
+        $(
+            impl From<$err> for Error {
+                fn from(err: $err) -> Self {
+                    Error::$variant(err)
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(
+    Parse(ParseError),
+    Validation(DocumentValidationError),
+    Conversion(ConversionError),
+    Patch(DiffPatchError),
+    Relationship(RelationshipAssumptionError),
+    Data(JsonApiDataError),
+    Query(QueryError)
+);
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Parse(ParseError::Json(err))
+    }
+}
@@ -0,0 +1,261 @@
+//! Defines the `JsonApiIdType` trait which converts the `id` of a model to and
+//! from the string used for `id` members in JSON:API documents.
+//!
+//! Implementations are provided for `String` and all integer types, and for
+//! `uuid::Uuid` when the `uuid` feature is enabled. Composite keys implement the
+//! trait themselves:
+//!
+//! ```rust
+//! #[macro_use] extern crate serde_derive;
+//! #[macro_use] extern crate jsonapi;
+//! use jsonapi::errors::*;
+//! use jsonapi::id::JsonApiIdType;
+//! use jsonapi::model::*;
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct ShelfPosition {
+//!     shelf: u32,
+//!     position: u32,
+//! }
+//!
+//! impl JsonApiIdType for ShelfPosition {
+//!     fn to_jsonapi_id(&self) -> String {
+//!         format!("{}-{}", self.shelf, self.position)
+//!     }
+//!
+//!     fn from_jsonapi_id(id: &str) -> Result<Self> {
+//!         let mut parts = id.splitn(2, '-').map(u32::from_jsonapi_id);
+//!         match (parts.next(), parts.next()) {
+//!             (Some(shelf), Some(position)) => Ok(ShelfPosition {
+//!                 shelf: shelf?,
+//!                 position: position?,
+//!             }),
+//!             _ => Err(ConversionError::InvalidResourceId(id.into(), "expected '<shelf>-<position>'".into()).into()),
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Book {
+//!     id: ShelfPosition,
+//!     title: String,
+//! }
+//! jsonapi_model!(Book; "books");
+//!
+//! let book = Book {
+//!     id: ShelfPosition { shelf: 3, position: 12 },
+//!     title: "The Hobbit".into(),
+//! };
+//! let (resource, included) = book.to_jsonapi_resource();
+//! assert_eq!(resource.id, "3-12");
+//! assert_eq!(Book::from_jsonapi_resource(&resource, &included).unwrap(), book);
+//! ```
+//!
+//! Relationship fields that only need linkage hold an [`Id`](struct.Id.html)
+//! of the related model or a `ResourceIdentifier` instead of the model. They
+//! are serialized without adding anything to `included`, and deserialized from
+//! the linkage alone:
+//!
+//! ```rust
+//! #[macro_use] extern crate serde_derive;
+//! #[macro_use] extern crate jsonapi;
+//! use jsonapi::id::Id;
+//! use jsonapi::model::*;
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Person {
+//!     id: u32,
+//!     name: String,
+//! }
+//! jsonapi_model!(Person; "people");
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Book {
+//!     id: u32,
+//!     title: String,
+//!     author: Id<Person>,
+//! }
+//! jsonapi_model!(Book; "books"; has one author);
+//!
+//! let book = Book {
+//!     id: 1,
+//!     title: "The Hobbit".into(),
+//!     author: Id::new(7),
+//! };
+//! let (resource, included) = book.to_jsonapi_resource();
+//! assert_eq!(included, Some(vec![]));
+//! assert_eq!(resource.get_relationship("author").unwrap().as_id().unwrap(), Some(&"7".to_string()));
+//! assert_eq!(Book::from_jsonapi_resource(&resource, &None).unwrap(), book);
+//! ```
+use crate::api::JsonApiId;
+use crate::errors::*;
+use crate::model::JsonApiModel;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+/// A type that can be used as the `id` of a model
+pub trait JsonApiIdType: Sized {
+    /// The `id` member of the resource identified by this value
+    fn to_jsonapi_id(&self) -> String;
+    /// Parse the `id` member of a resource
+    fn from_jsonapi_id(id: &str) -> Result<Self>;
+}
+
+/// The identifier of a related resource of the model `T`, whose `type` is
+/// the one of the model. The `id` member is checked to be a valid `id` of the
+/// model when deserialized.
+pub struct Id<T: JsonApiModel> {
+    pub id: JsonApiId,
+    model: PhantomData<T>,
+}
+
+impl<T: JsonApiModel> Id<T> {
+    /// The identifier of the resource of the model with the given `id`
+    pub fn new<I: JsonApiIdType>(id: I) -> Self {
+        Id {
+            id: id.to_jsonapi_id(),
+            model: PhantomData,
+        }
+    }
+
+    /// The identifier of the resource of `model`
+    pub fn of(model: &T) -> Self {
+        Id {
+            id: model.jsonapi_id(),
+            model: PhantomData,
+        }
+    }
+
+    /// The `type` of the identified resource
+    pub fn type_name(&self) -> &'static str {
+        T::jsonapi_type_name()
+    }
+}
+
+// Implemented by hand, since deriving would require `T` to implement them too
+
+impl<T: JsonApiModel> Clone for Id<T> {
+    fn clone(&self) -> Self {
+        Id {
+            id: self.id.clone(),
+            model: PhantomData,
+        }
+    }
+}
+
+impl<T: JsonApiModel> fmt::Debug for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Id")
+            .field("type", &T::jsonapi_type_name())
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl<T: JsonApiModel> PartialEq for Id<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T: JsonApiModel> Eq for Id<T> {}
+
+impl<T: JsonApiModel> Hash for Id<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+impl<T: JsonApiModel> PartialOrd for Id<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: JsonApiModel> Ord for Id<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+/// Serialized as a resource identifier object
+impl<T: JsonApiModel> Serialize for Id<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        let mut identifier = serializer.serialize_struct("Id", 2)?;
+        identifier.serialize_field("type", T::jsonapi_type_name())?;
+        identifier.serialize_field("id", &self.id)?;
+        identifier.end()
+    }
+}
+
+/// Deserialized from a resource identifier object, failing when its `type` is
+/// not the one of the model or its `id` is not a valid `id` of the model
+impl<'de, T: JsonApiModel> Deserialize<'de> for Id<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Identifier {
+            #[serde(rename = "type")]
+            _type: String,
+            id: JsonApiId,
+        }
+
+        let identifier = Identifier::deserialize(deserializer)?;
+        if identifier._type != T::jsonapi_type_name() {
+            return Err(de::Error::invalid_value(
+                de::Unexpected::Str(&identifier._type),
+                &T::jsonapi_type_name(),
+            ));
+        }
+        T::jsonapi_id_value(&identifier.id).map_err(de::Error::custom)?;
+        Ok(Id {
+            id: identifier.id,
+            model: PhantomData,
+        })
+    }
+}
+
+impl JsonApiIdType for String {
+    fn to_jsonapi_id(&self) -> String {
+        self.clone()
+    }
+
+    fn from_jsonapi_id(id: &str) -> Result<Self> {
+        Ok(id.to_string())
+    }
+}
+
+macro_rules! impl_integer_id {
+    ($($t:ty),*) => {
+        $(
+            impl JsonApiIdType for $t {
+                fn to_jsonapi_id(&self) -> String {
+                    self.to_string()
+                }
+
+                fn from_jsonapi_id(id: &str) -> Result<Self> {
+                    id.parse().map_err(|err: ::std::num::ParseIntError| {
+                        ConversionError::InvalidResourceId(id.to_string(), err.to_string()).into()
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_id!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+#[cfg(feature = "uuid")]
+impl JsonApiIdType for uuid::Uuid {
+    fn to_jsonapi_id(&self) -> String {
+        self.hyphenated().to_string()
+    }
+
+    fn from_jsonapi_id(id: &str) -> Result<Self> {
+        uuid::Uuid::parse_str(id)
+            .map_err(|err| ConversionError::InvalidResourceId(id.to_string(), err.to_string()).into())
+    }
+}
@@ -16,9 +16,10 @@
 //!
 //! [JSON:API]: https://jsonapi.org/
 //! [serde]: https://serde.rs
-//! [JsonApiDocument]: api/struct.JsonApiDocument.html
-//! [Resource]: api/struct.Resource.html
+//! [JsonApiDocument]: api/type.JsonApiDocument.html
+//! [Resource]: api/type.Resource.html
 //! [jsonapi_model]: macro.jsonapi_model.html
+//! [JsonApiIdType]: id/trait.JsonApiIdType.html
 //!
 //! ## Examples
 //!
@@ -26,7 +27,9 @@
 //!
 //! Using the [`jsonapi_model!`][jsonapi_model] macro a struct can be converted
 //! into a [`JsonApiDocument`][JsonApiDocument] or [`Resource`][Resource]. It is
-//! required that the struct have an `id` property whose type is `String`. The
+//! required that the struct have an `id` property whose type implements
+//! [`JsonApiIdType`][JsonApiIdType], such as `String`, the integer types or,
+//! with the `uuid` feature, `uuid::Uuid`. The
 //! second argument in the [`jsonapi_model!`][jsonapi_model] marco defines the
 //! `type` member as required by the [JSON:API] specification
 //!
@@ -63,6 +66,10 @@
 //! variable type in `Result`
 //!
 //! ```rust
+//! # extern crate serde_json;
+//! # extern crate jsonapi;
+//! use jsonapi::api::*;
+//!
 //! let serialized = r#"
 //! {
 //!   "data": [{
@@ -88,15 +95,43 @@
 //!     }
 //!   ]
 //! }"#;
-//! let data: Result<Resource, serde_json::Error> = serde_json::from_str(&serialized);
+//! let data: Result<JsonApiDocument, serde_json::Error> = serde_json::from_str(&serialized);
 //! assert_eq!(data.is_ok(), true);
 //! ```
 //!
 //! Or parse the `String` directly using the
-//! [Resource::from_str](api/struct.Resource.html) trait implementation
+//! [JsonApiDocument::from_str](api/type.JsonApiDocument.html) trait implementation
 //!
 //! ```rust
-//! let data = Resource::from_str(&serialized);
+//! # use jsonapi::api::*;
+//! use std::str::FromStr;
+//!
+//! # let serialized = r#"
+//! # {
+//! #   "data": [{
+//! #     "type": "articles",
+//! #     "id": "1",
+//! #     "attributes": {
+//! #       "title": "JSON:API paints my bikeshed!",
+//! #       "body": "The shortest article. Ever."
+//! #     },
+//! #     "relationships": {
+//! #       "author": {
+//! #         "data": {"id": "42", "type": "people"}
+//! #       }
+//! #     }
+//! #   }],
+//! #   "included": [
+//! #     {
+//! #       "type": "people",
+//! #       "id": "42",
+//! #       "attributes": {
+//! #         "name": "John"
+//! #       }
+//! #     }
+//! #   ]
+//! # }"#;
+//! let data = JsonApiDocument::from_str(&serialized);
 //! assert_eq!(data.is_ok(), true);
 //! ```
 //!
@@ -132,15 +167,29 @@ extern crate serde_json;
 extern crate serde_derive;
 
 extern crate queryst;
+extern crate serde_path_to_error;
+
+#[cfg(feature = "uuid")]
+extern crate uuid;
+#[cfg(feature = "smallvec")]
+extern crate smallvec;
+#[cfg(feature = "indexmap")]
+extern crate indexmap;
 
 #[macro_use]
 extern crate log;
 
-#[macro_use]
-extern crate error_chain;
 
 pub mod api;
+pub mod document;
 pub mod array;
 pub mod query;
 pub mod model;
 pub mod errors;
+pub mod id;
+pub mod links;
+pub mod naming;
+pub mod related;
+mod de;
+mod pointer;
+mod ser;
@@ -0,0 +1,115 @@
+//! Defines the `LinkTemplates` used to generate the `links` of resources and
+//! their relationships.
+//!
+//! A template is a path in which `{type}`, `{id}` and `{name}` are replaced by
+//! the `type` and `id` of the resource and the member name of the
+//! relationship, percent-encoded as path segments. The `base_url` of the
+//! [`ConversionContext`](../model/struct.ConversionContext.html) is prepended to
+//! the expanded path.
+//!
+//! ```rust
+//! use jsonapi::links::LinkTemplates;
+//!
+//! let templates = LinkTemplates::default();
+//! let links = templates
+//!     .relationship_links(Some("https://example.com"), "articles", "1", "author")
+//!     .unwrap();
+//! assert_eq!(links["self"], "https://example.com/articles/1/relationships/author");
+//! assert_eq!(links["related"], "https://example.com/articles/1/author");
+//! ```
+use crate::api::{JsonApiValue, Links};
+
+/// Templates for the links generated for resources and relationships. A link
+/// whose template is `None` is left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkTemplates {
+    /// The `self` link of a resource, `/{type}/{id}` by default
+    pub resource: Option<String>,
+    /// The `self` link of a relationship,
+    /// `/{type}/{id}/relationships/{name}` by default
+    pub relationship: Option<String>,
+    /// The `related` link of a relationship, `/{type}/{id}/{name}` by default
+    pub related: Option<String>,
+}
+
+impl Default for LinkTemplates {
+    fn default() -> Self {
+        LinkTemplates {
+            resource: Some("/{type}/{id}".into()),
+            relationship: Some("/{type}/{id}/relationships/{name}".into()),
+            related: Some("/{type}/{id}/{name}".into()),
+        }
+    }
+}
+
+impl LinkTemplates {
+    /// The links of the resource with the given `_type` and `id`
+    pub fn resource_links(&self, base_url: Option<&str>, _type: &str, id: &str) -> Option<Links> {
+        links(vec![(
+            "self",
+            self.resource.as_ref().map(|template| expand(template, base_url, _type, id, "")),
+        )])
+    }
+
+    /// The links of the relationship `name` of the resource with the given
+    /// `_type` and `id`
+    pub fn relationship_links(&self, base_url: Option<&str>, _type: &str, id: &str, name: &str)
+        -> Option<Links>
+    {
+        links(vec![
+            ("self", self.relationship.as_ref().map(|template| expand(template, base_url, _type, id, name))),
+            ("related", self.related.as_ref().map(|template| expand(template, base_url, _type, id, name))),
+        ])
+    }
+}
+
+/// Replaces the placeholders of `template` in a single pass, so that values
+/// are never expanded again, and prepends `base_url`
+fn expand(template: &str, base_url: Option<&str>, _type: &str, id: &str, name: &str) -> String {
+    let placeholders = [("{type}", _type), ("{id}", id), ("{name}", name)];
+    let mut path = String::with_capacity(template.len() + id.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        path.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match placeholders.iter().find(|&&(placeholder, _)| rest.starts_with(placeholder)) {
+            Some(&(placeholder, value)) => {
+                encode_segment(value, &mut path);
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                path.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    path.push_str(rest);
+    match base_url {
+        Some(base) => format!("{}/{}", base.trim_end_matches('/'), path.trim_start_matches('/')),
+        None => path,
+    }
+}
+
+/// Appends `value` to `path` as a path segment, percent-encoding every byte
+/// that is not allowed in one (RFC 3986)
+fn encode_segment(value: &str, path: &mut String) {
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'!' | b'$' | b'&' | b'\''
+            | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' | b':' | b'@' => path.push(byte as char),
+            _ => path.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+}
+
+fn links(urls: Vec<(&str, Option<String>)>) -> Option<Links> {
+    let links: Links = urls
+        .into_iter()
+        .filter_map(|(name, url)| url.map(|url| (name.to_string(), JsonApiValue::String(url))))
+        .collect();
+    if links.is_empty() {
+        None
+    } else {
+        Some(links)
+    }
+}
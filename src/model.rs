@@ -1,25 +1,38 @@
 //! Defines the `JsonApiModel` trait. This is primarily used in conjunction with
 //! the [`jsonapi_model!`](../macro.jsonapi_model.html) macro to allow arbitrary
 //! structs which implement `Deserialize` to be converted to/from a
-//! [`JsonApiDocument`](../api/struct.JsonApiDocument.html) or
-//! [`Resource`](../api/struct.Resource.html)
+//! [`JsonApiDocument`](../api/type.JsonApiDocument.html) or
+//! [`Resource`](../api/type.Resource.html)
 pub use std::collections::HashMap;
 pub use crate::api::*;
+pub use crate::array::{JsonApiArray, JsonApiArrayMut};
+use crate::de::{self, ResourceDeserializer};
 use crate::errors::*;
+use crate::id::JsonApiIdType;
+pub use crate::links::LinkTemplates;
+pub use crate::naming::NamingPolicy;
+use crate::related::JsonApiRelated;
+use crate::ser;
 use serde::{Deserialize, Serialize};
-use serde_json::{from_value, to_value, Value, Map};
+use serde_json::{to_value, Value};
+use std::borrow::Cow;
 
 /// A trait for any struct that can be converted from/into a
 /// [`Resource`](api/struct.Resource.tml). The only requirement is that your
-/// struct has an `id: String` field.
+/// struct has an `id` field whose type implements
+/// [`JsonApiIdType`](../id/trait.JsonApiIdType.html).
 /// You shouldn't be implementing JsonApiModel manually, look at the
 /// `jsonapi_model!` macro instead.
 pub trait JsonApiModel: Serialize
 where
     for<'de> Self: Deserialize<'de>,
 {
+    /// The `type` of the resources of this model
+    fn jsonapi_type_name() -> &'static str;
     #[doc(hidden)]
-    fn jsonapi_type(&self) -> String;
+    fn jsonapi_type(&self) -> String {
+        Self::jsonapi_type_name().to_string()
+    }
     #[doc(hidden)]
     fn jsonapi_id(&self) -> String;
     #[doc(hidden)]
@@ -27,90 +40,238 @@ where
     #[doc(hidden)]
     fn build_relationships(&self) -> Option<Relationships>;
     #[doc(hidden)]
-    fn build_included(&self) -> Option<Resources>;
+    fn build_included(&self, ctx: &ConversionContext) -> Result<Option<Resources>>;
+
+    /// The naming policy for the members of this model. Returning `None`, the
+    /// default, uses the policy of the
+    /// [`ConversionContext`](struct.ConversionContext.html). Override it in the
+    /// trailing block of the [`jsonapi_model!`](../macro.jsonapi_model.html)
+    /// macro.
+    fn naming_policy() -> Option<NamingPolicy> {
+        None
+    }
+
+    /// The link templates of this model. Returning `None`, the default, uses
+    /// the templates of the [`ConversionContext`](struct.ConversionContext.html).
+    fn link_templates() -> Option<LinkTemplates> {
+        None
+    }
+
+    /// The `links` member of the resource. By default the `self` link
+    /// generated from the [`LinkTemplates`](../links/struct.LinkTemplates.html)
+    /// that apply to the model.
+    fn jsonapi_links(&self, ctx: &ConversionContext) -> Option<Links> {
+        let model_templates = Self::link_templates();
+        ctx.templates_for(model_templates.as_ref())?
+            .resource_links(ctx.base_url.as_deref(), &self.jsonapi_type(), &self.jsonapi_id())
+    }
+
+    /// The `links` member of the relationship `name` of the resource, where
+    /// `name` is the member name of the relationship. By default the `self`
+    /// and `related` links generated from the
+    /// [`LinkTemplates`](../links/struct.LinkTemplates.html) that apply to the
+    /// model.
+    fn jsonapi_relationship_links(&self, name: &str, ctx: &ConversionContext) -> Option<Links> {
+        let model_templates = Self::link_templates();
+        ctx.templates_for(model_templates.as_ref())?
+            .relationship_links(ctx.base_url.as_deref(), &self.jsonapi_type(), &self.jsonapi_id(), name)
+    }
+
+    /// The `meta` member of the resource, `None` by default
+    fn jsonapi_meta(&self) -> Option<Meta> {
+        None
+    }
+
+    /// Converts the `id` member of a resource into the value the `id` field
+    /// of the struct deserializes from
+    #[doc(hidden)]
+    fn jsonapi_id_value(id: &str) -> Result<Value> {
+        Ok(Value::String(id.to_string()))
+    }
+
+    /// Describes the model on the other side of the relationship `name`
+    #[doc(hidden)]
+    fn relationship_descriptor(_name: &str) -> Option<ModelDescriptor> {
+        None
+    }
+
+    #[doc(hidden)]
+    fn descriptor() -> ModelDescriptor {
+        ModelDescriptor {
+            id_value: Self::jsonapi_id_value,
+            relationship: Self::relationship_descriptor,
+            naming: Self::naming_policy(),
+            type_name: Some(Self::jsonapi_type_name()),
+            ..Default::default()
+        }
+    }
 
     fn from_jsonapi_resource(resource: &Resource, included: &Option<Resources>)
         -> Result<Self>
     {
+        Self::from_jsonapi_resource_with(resource, included, &ConversionContext::default())
+    }
 
-        let visited_relationships: Vec<&str> = Vec::new();
-        Self::from_serializable(Self::resource_to_attrs(resource, included, &visited_relationships))
+    /// Like [`from_jsonapi_resource`](#method.from_jsonapi_resource), reading
+    /// member names and checking the resource according to `ctx`
+    fn from_jsonapi_resource_with(resource: &Resource, included: &Option<Resources>, ctx: &ConversionContext)
+        -> Result<Self>
+    {
+        let index = included.as_ref().map(|inc| IncludedIndex::new(inc));
+        Self::deserialize(ResourceDeserializer::new(resource, index.as_ref(), ctx, Self::descriptor(), String::new()))
+            .map_err(Error::from)
     }
 
     /// Create a single resource object or collection of resource
     /// objects directly from 
-    /// [`DocumentData`](../api/struct.DocumentData.html). This method
+    /// [`DocumentData`](../api/type.DocumentData.html). This method
     /// will parse the document (the `data` and `included` resources) in an
-    /// attempt to instantiate the calling struct.
-    fn from_jsonapi_document(doc: &DocumentData) -> Result<Self> {
+    /// attempt to instantiate the calling struct. The top-level `meta` of the
+    /// document can be of any type.
+    fn from_jsonapi_document(doc: &GenericDocumentData<impl Sized, Meta>) -> Result<Self> {
+        Self::from_jsonapi_document_with(doc, &ConversionContext::default())
+    }
+
+    /// Like [`from_jsonapi_document`](#method.from_jsonapi_document), reading
+    /// member names and checking the resources according to `ctx`
+    fn from_jsonapi_document_with(doc: &GenericDocumentData<impl Sized, Meta>, ctx: &ConversionContext) -> Result<Self> {
         match doc.data.as_ref() {
             Some(primary_data) => {
                 match *primary_data {
-                    PrimaryData::None => bail!("Document had no data"),
+                    PrimaryData::None => Err(ConversionError::NoData.into()),
                     PrimaryData::Single(ref resource) => {
-                        Self::from_jsonapi_resource(resource, &doc.included)
+                        let index = doc.included.as_ref().map(|inc| IncludedIndex::new(inc));
+                        let pointer = "/data".to_string();
+                        Self::deserialize(ResourceDeserializer::new(resource, index.as_ref(), ctx, Self::descriptor(), pointer))
+                            .map_err(Error::from)
                     }
                     PrimaryData::Multiple(ref resources) => {
-                        let visited_relationships: Vec<&str> = Vec::new();
-                        let all: Vec<ResourceAttributes> = resources
+                        let index = doc.included.as_ref().map(|inc| IncludedIndex::new(inc));
+                        let resources = resources
                             .iter()
-                            .map(|r| Self::resource_to_attrs(r, &doc.included, &visited_relationships))
-                            .collect();
-                        Self::from_serializable(all)
+                            .enumerate()
+                            .map(|(i, resource)| (format!("/data/{}", i), resource));
+                        Self::deserialize(de::resources(resources, index.as_ref(), ctx, Self::descriptor()))
+                            .map_err(Error::from)
                     }
                 }
             }
-            None => bail!("Document had no data"),
+            None => Err(ConversionError::NoData.into()),
         }
     }
 
     /// Converts the instance of the struct into a
-    /// [`Resource`](../api/struct.Resource.html)
-    fn to_jsonapi_resource(&self) -> (Resource, Option<Resources>) {
-        if let Value::Object(mut attrs) = to_value(self).unwrap() {
-            let _ = attrs.remove("id");
-            let resource = Resource {
-                _type: self.jsonapi_type(),
-                id: self.jsonapi_id(),
-                relationships: self.build_relationships(),
-                attributes: Self::extract_attributes(&attrs),
-                ..Default::default()
-            };
+    /// [`Resource`](../api/type.Resource.html) and the resources to be
+    /// included alongside it.
+    ///
+    /// Returns an error naming the model, and the attribute where known, when
+    /// the struct does not serialize to an object or one of its attributes
+    /// cannot be serialized.
+    fn try_to_jsonapi_resource(&self) -> Result<(Resource, Option<Resources>)> {
+        self.try_to_jsonapi_resource_with(&ConversionContext::default())
+    }
 
-            (resource, self.build_included())
-        } else {
-            panic!(format!("{} is not a Value::Object", self.jsonapi_type()))
+    /// Like [`try_to_jsonapi_resource`](#method.try_to_jsonapi_resource),
+    /// naming members and applying sparse fieldsets according to `ctx`. The
+    /// included resources are converted with the same `ctx`.
+    fn try_to_jsonapi_resource_with(&self, ctx: &ConversionContext)
+        -> Result<(Resource, Option<Resources>)>
+    {
+        let _type = self.jsonapi_type();
+        let naming = Self::naming_policy().unwrap_or(ctx.naming);
+        let mut attributes = ser::to_attributes(self, Self::relationship_fields(), naming)
+            .map_err(|err| err.for_model(_type.clone()))?;
+        let mut relationships = self.build_relationships().map(|relationships| {
+            relationships
+                .into_iter()
+                .map(|(name, relationship)| (naming.apply(&name), relationship))
+                .collect::<Relationships>()
+        });
+        if let Some(fields) = ctx.fieldset(&_type) {
+            attributes.retain(|name, _| fields.contains(name));
+            if let Some(ref mut relationships) = relationships {
+                relationships.retain(|name, _| fields.contains(name));
+            }
+        }
+        if let Some(ref mut relationships) = relationships {
+            for (name, relationship) in relationships.iter_mut() {
+                if relationship.links.is_none() {
+                    relationship.links = self.jsonapi_relationship_links(name, ctx);
+                }
+            }
         }
+        let resource = Resource {
+            _type,
+            id: self.jsonapi_id(),
+            relationships,
+            attributes,
+            links: self.jsonapi_links(ctx),
+            meta: self.jsonapi_meta(),
+        };
+
+        Ok((resource, self.build_included(ctx)?))
     }
 
+    /// Converts the instance of the struct into a
+    /// [`Resource`](../api/type.Resource.html)
+    ///
+    /// # Panics
+    ///
+    /// Panics when the conversion fails, see
+    /// [`try_to_jsonapi_resource`](#method.try_to_jsonapi_resource)
+    fn to_jsonapi_resource(&self) -> (Resource, Option<Resources>) {
+        self.try_to_jsonapi_resource()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
 
     /// Converts the struct into a complete
-    /// [`JsonApiDocument`](../api/struct.JsonApiDocument.html)
-    fn to_jsonapi_document(&self) -> JsonApiDocument {
-        let (resource, included) = self.to_jsonapi_resource();
-        JsonApiDocument::Data (
+    /// [`JsonApiDocument`](../api/type.JsonApiDocument.html), returning an
+    /// error when the conversion fails
+    fn try_to_jsonapi_document(&self) -> Result<JsonApiDocument> {
+        self.try_to_jsonapi_document_with(&ConversionContext::default())
+    }
+
+    /// Like [`try_to_jsonapi_document`](#method.try_to_jsonapi_document),
+    /// converting the resources according to `ctx`
+    fn try_to_jsonapi_document_with(&self, ctx: &ConversionContext) -> Result<JsonApiDocument> {
+        let (resource, included) = self.try_to_jsonapi_resource_with(ctx)?;
+        Ok(JsonApiDocument::Data (
             DocumentData {
                 data: Some(PrimaryData::Single(Box::new(resource))),
                 included,
                 ..Default::default()
             }
-        )
+        ))
     }
 
+    /// Converts the struct into a complete
+    /// [`JsonApiDocument`](../api/type.JsonApiDocument.html)
+    ///
+    /// # Panics
+    ///
+    /// Panics when the conversion fails, see
+    /// [`try_to_jsonapi_document`](#method.try_to_jsonapi_document)
+    fn to_jsonapi_document(&self) -> JsonApiDocument {
+        self.try_to_jsonapi_document()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
 
     #[doc(hidden)]
-    fn build_has_one<M: JsonApiModel>(model: &M) -> Relationship {
+    fn build_has_one<M: JsonApiRelated>(model: &M) -> Relationship {
         Relationship {
-            data: Some(IdentifierData::Single(model.as_resource_identifier())),
+            data: Some(match model.related_identifier() {
+                Some(identifier) => IdentifierData::Single(identifier),
+                None => IdentifierData::None,
+            }),
             links: None
         }
     }
 
     #[doc(hidden)]
-    fn build_has_many<M: JsonApiModel>(models: &[M]) -> Relationship {
+    fn build_has_many<'a, M: JsonApiRelated + 'a, I: IntoIterator<Item = &'a M>>(models: I) -> Relationship {
         Relationship {
             data: Some(IdentifierData::Multiple(
-                models.iter().map(|m| m.as_resource_identifier()).collect()
+                models.into_iter().filter_map(|m| m.related_identifier()).collect()
             )),
             links: None
         }
@@ -124,127 +285,260 @@ where
         }
     }
 
-    /* Attribute corresponding to the model is removed from the Map
-     * before calling this, so there's no need to ignore it like we do
-     * with the attributes that correspond with relationships.
-     * */
     #[doc(hidden)]
-    fn extract_attributes(attrs: &Map<String, Value>) -> ResourceAttributes {
-        attrs
-            .iter()
-            .filter(|&(key, _)| {
-                if let Some(fields) = Self::relationship_fields() {
-                    if fields.contains(&key.as_str()) {
-                        return false;
-                    }
-                }
-                true
-            })
-            .map(|(k, v)| (k.clone(), v.clone()))
-            .collect()
-    }
-
-    #[doc(hidden)]
-    fn to_resources(&self) -> Resources {
-        let (me, maybe_others) = self.to_jsonapi_resource();
+    fn to_resources(&self, ctx: &ConversionContext) -> Result<Resources> {
+        let (me, maybe_others) = self.try_to_jsonapi_resource_with(ctx)?;
         let mut flattened = vec![me];
         if let Some(mut others) = maybe_others {
             flattened.append(&mut others);
         }
-        flattened
+        Ok(flattened)
     }
+}
 
-    /// When passed a `ResourceIdentifier` (which contains a `type` and `id`)
-    /// this will iterate through the collection provided `haystack` in an
-    /// attempt to find and return the `Resource` whose `type` and `id`
-    /// attributes match
-    #[doc(hidden)]
-    fn lookup<'a>(needle: &ResourceIdentifier, haystack: &'a [Resource])
-        -> Option<&'a Resource>
-    {
-        for resource in haystack {
-            if resource._type == needle._type && resource.id == needle.id {
-                return Some(resource);
-            }
+/// Describes how the resources of a model are deserialized: how its `id` is
+/// converted, the resource type it expects, which models its relationships
+/// point to, how its members are named, for polymorphic relationships which
+/// enum variant holds each resource type, and whether the model is only the
+/// linkage of a relationship.
+/// Descriptors are
+/// built by the [`jsonapi_model!`](../macro.jsonapi_model.html) macro and
+/// passed down as nested resources are deserialized.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+pub struct ModelDescriptor {
+    pub id_value: fn(&str) -> Result<Value>,
+    pub type_name: Option<&'static str>,
+    pub relationship: fn(&str) -> Option<ModelDescriptor>,
+    pub naming: Option<NamingPolicy>,
+    pub variant: fn(&str) -> Option<(&'static str, ModelDescriptor)>,
+    pub linkage: bool,
+}
+
+fn string_id_value(id: &str) -> Result<Value> {
+    Ok(Value::String(id.to_string()))
+}
+
+fn no_relationship(_name: &str) -> Option<ModelDescriptor> {
+    None
+}
+
+fn no_variant(_type: &str) -> Option<(&'static str, ModelDescriptor)> {
+    None
+}
+
+impl Default for ModelDescriptor {
+    /// A model with a `String` id and no known relationships
+    fn default() -> Self {
+        ModelDescriptor {
+            id_value: string_id_value,
+            type_name: None,
+            relationship: no_relationship,
+            naming: None,
+            variant: no_variant,
+            linkage: false,
         }
-        None
     }
+}
 
-    /// Return a [`ResourceAttributes`](../api/struct.ResourceAttributes.html)
-    /// object that contains the attributes in this `resource`. This will be
-    /// called recursively for each `relationship` on the resource in an attempt
-    /// to satisfy the properties for the calling struct.
-    ///
-    /// The last parameter in this function call is `visited_relationships` which is used as this
-    /// function is called recursively. This `Vec` contains the JSON:API `relationships` that were
-    /// visited when this function was called last. When operating on the root node of the document
-    /// this is simply started with an empty `Vec`.
-    ///
-    /// Tracking these "visited" relationships is necessary to prevent infinite recursion and stack
-    /// overflows. This situation can arise when the "included" resource object includes the parent
-    /// resource object - it will simply ping pong back and forth unable to acheive a finite
-    /// resolution.
-    ///
-    /// The JSON:API specification doesn't communicate the direction of a relationship.
-    /// Furthermore the current implementation of this crate does not establish an object graph
-    /// that could be used to traverse these relationships effectively.
-    #[doc(hidden)]
-    fn resource_to_attrs(resource: &Resource, included: &Option<Resources>, visited_relationships: &Vec<&str>)
-        -> ResourceAttributes
-    {
-        let mut new_attrs = HashMap::new();
-        new_attrs.clone_from(&resource.attributes);
-        new_attrs.insert("id".into(), resource.id.clone().into());
-
-        // Copy the contents of `visited_relationships` so that we can mutate within the lexical
-        // scope of this function call. This is also important so each edge that we follow (the
-        // relationship) is not polluted by data from traversing sibling relationships
-        let mut this_visited: Vec<&str> = Vec::new();
-        for rel in visited_relationships.iter() {
-            this_visited.push(rel);
+/// Options for converting models to and from resources, accepted by the
+/// `_with` variants of the conversion methods of
+/// [`JsonApiModel`](trait.JsonApiModel.html)
+///
+/// ```rust
+/// #[macro_use] extern crate serde_derive;
+/// #[macro_use] extern crate jsonapi;
+/// use jsonapi::model::*;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Person {
+///     id: String,
+///     first_name: String,
+///     last_name: String,
+/// }
+/// jsonapi_model!(Person; "people");
+///
+/// let ctx = ConversionContext {
+///     naming: NamingPolicy::CamelCase,
+///     ..Default::default()
+/// };
+/// let person = Person {
+///     id: "1".into(),
+///     first_name: "Ada".into(),
+///     last_name: "Lovelace".into(),
+/// };
+/// let (resource, included) = person.try_to_jsonapi_resource_with(&ctx).unwrap();
+/// assert!(resource.attributes.contains_key("firstName"));
+/// assert_eq!(Person::from_jsonapi_resource_with(&resource, &included, &ctx).unwrap(), person);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionContext {
+    /// The naming policy of models that do not declare their own
+    pub naming: NamingPolicy,
+    /// Sparse fieldsets keyed by resource type, as parsed into
+    /// [`Query::fields`](../query/struct.Query.html). Only the listed members
+    /// of resources of these types are serialized.
+    pub fields: Option<HashMap<String, Vec<String>>>,
+    /// The URL resources are served below, such as `https://example.com/api`,
+    /// prepended to generated links
+    pub base_url: Option<String>,
+    /// The templates links are generated from. When neither these nor the
+    /// templates of a model are set, links are generated from the default
+    /// templates if there is a `base_url`, and not at all otherwise.
+    pub link_templates: Option<LinkTemplates>,
+    /// Reject resources, including related ones, whose `type` differs from
+    /// the [`jsonapi_type_name`](trait.JsonApiModel.html#tymethod.jsonapi_type_name)
+    /// of the model. Enabled by default.
+    pub check_type: bool,
+    /// Fail on relationship linkage to resources missing from `included`,
+    /// instead of deserializing them as `null`
+    pub require_included: bool,
+    /// Fail on attributes that match no attribute field of the model
+    pub deny_unknown_attributes: bool,
+    /// Fail on attribute fields of the model that are missing from the
+    /// resource, instead of leaving them to serde
+    pub deny_missing_attributes: bool,
+    /// With `deny_missing_attributes`, still allow attributes of `Option`
+    /// fields to be missing, deserializing them as `None`
+    pub allow_missing_optional_attributes: bool,
+}
+
+impl Default for ConversionContext {
+    fn default() -> Self {
+        ConversionContext {
+            naming: NamingPolicy::default(),
+            fields: None,
+            base_url: None,
+            link_templates: None,
+            check_type: true,
+            require_included: false,
+            deny_unknown_attributes: false,
+            deny_missing_attributes: false,
+            allow_missing_optional_attributes: false,
         }
+    }
+}
 
-        if let Some(relations) = resource.relationships.as_ref() {
-            if let Some(inc) = included.as_ref() {
-                for (name, relation) in relations {
-                    // If we have already visited this resource object, exit early and do not
-                    // recurse through the relations
-                    if this_visited.contains(&name.as_str()) {
-                        return new_attrs;
-                    }
-                    // Track that we have visited this relationship to avoid infinite recursion
-                    this_visited.push(name);
-
-                    let value = match relation.data {
-                        Some(IdentifierData::None) => Value::Null,
-                        Some(IdentifierData::Single(ref identifier)) => {
-                            let found = Self::lookup(identifier, inc)
-                                .map(|r| Self::resource_to_attrs(r, included, &this_visited) );
-                            to_value(found)
-                                .expect("Casting Single relation to value")
-                        },
-                        Some(IdentifierData::Multiple(ref identifiers)) => {
-                            let found: Vec<Option<ResourceAttributes>> =
-                                identifiers.iter().map(|identifier|{
-                                    Self::lookup(identifier, inc).map(|r|{
-                                        Self::resource_to_attrs(r, included, &this_visited)
-                                    })
-                                }).collect();
-                            to_value(found)
-                                .expect("Casting Multiple relation to value")
-                        },
-                        None => Value::Null,
-                    };
-                    new_attrs.insert(name.to_string(), value);
-                }
-            }
+impl ConversionContext {
+    /// A context that checks resources against models as strictly as
+    /// possible, allowing only attributes of `Option` fields to be missing
+    pub fn strict() -> Self {
+        ConversionContext {
+            require_included: true,
+            deny_unknown_attributes: true,
+            deny_missing_attributes: true,
+            allow_missing_optional_attributes: true,
+            ..Default::default()
         }
-        new_attrs
     }
 
-    #[doc(hidden)]
-    fn from_serializable<S: Serialize>(s: S) -> Result<Self> {
-        from_value(to_value(s)?).map_err(Error::from)
+    /// The sparse fieldset requested for resources of type `_type`
+    pub fn fieldset(&self, _type: &str) -> Option<&Vec<String>> {
+        self.fields.as_ref().and_then(|fields| fields.get(_type))
+    }
+
+    /// The link templates that apply to a model whose own templates are
+    /// `model`
+    pub fn templates_for<'a>(&'a self, model: Option<&'a LinkTemplates>) -> Option<Cow<'a, LinkTemplates>> {
+        match model.or(self.link_templates.as_ref()) {
+            Some(templates) => Some(Cow::Borrowed(templates)),
+            None if self.base_url.is_some() => Some(Cow::Owned(LinkTemplates::default())),
+            None => None,
+        }
+    }
+}
+
+/// Converts `id` using the type of the field `field` points to
+#[doc(hidden)]
+pub fn id_value_of<M, I, F>(_field: F, id: &str) -> Result<Value>
+where
+    I: JsonApiIdType + Serialize,
+    F: Fn(&M) -> &I,
+{
+    to_value(I::from_jsonapi_id(id)?).map_err(Error::from)
+}
+
+/// The descriptor of the value held by the `has one` field `field` points to
+#[doc(hidden)]
+pub fn descriptor_of_one<M, R, F>(_field: F) -> ModelDescriptor
+where
+    R: JsonApiRelated,
+    F: Fn(&M) -> &R,
+{
+    R::related_descriptor()
+}
+
+/// The descriptor of the values held by the `has many` field `field` points to
+#[doc(hidden)]
+pub fn descriptor_of_many<M, R, C, F>(_field: F) -> ModelDescriptor
+where
+    R: JsonApiRelated,
+    C: JsonApiArray<R>,
+    F: Fn(&M) -> &C,
+{
+    R::related_descriptor()
+}
+
+/// An index over the `included` resources of a document, keyed by `type` and
+/// `id`. Building the index is linear in the number of included resources,
+/// after which resolving a [`ResourceIdentifier`](../api/struct.ResourceIdentifier.html)
+/// is a constant time lookup.
+///
+/// ```rust
+/// use jsonapi::model::*;
+///
+/// let included = vec![Resource {
+///     _type: "people".into(),
+///     id: "42".into(),
+///     ..Default::default()
+/// }];
+/// let index = IncludedIndex::new(&included);
+///
+/// let identifier = ResourceIdentifier { _type: "people".into(), id: "42".into() };
+/// assert_eq!(index.get(&identifier), Some(&included[0]));
+/// ```
+#[derive(Debug, Default)]
+pub struct IncludedIndex<'a> {
+    resources: HashMap<&'a str, HashMap<&'a str, (usize, &'a Resource)>>,
+}
+
+impl<'a> IncludedIndex<'a> {
+    /// Build an index over `included`. When a `type` and `id` pair occurs more
+    /// than once the first occurrence wins, as it would for a linear scan.
+    pub fn new(included: &'a [Resource]) -> Self {
+        let mut resources: HashMap<&'a str, HashMap<&'a str, (usize, &'a Resource)>> = HashMap::new();
+        for (position, resource) in included.iter().enumerate() {
+            resources
+                .entry(resource._type.as_str())
+                .or_default()
+                .entry(resource.id.as_str())
+                .or_insert((position, resource));
+        }
+        IncludedIndex { resources }
+    }
+
+    /// Find the included `Resource` identified by `identifier`
+    pub fn get(&self, identifier: &ResourceIdentifier) -> Option<&'a Resource> {
+        self.locate(identifier).map(|(_, resource)| resource)
+    }
+
+    /// Find the included `Resource` identified by `identifier` along with its
+    /// position in `included`
+    pub fn locate(&self, identifier: &ResourceIdentifier) -> Option<(usize, &'a Resource)> {
+        self.resources
+            .get(identifier._type.as_str())
+            .and_then(|by_id| by_id.get(identifier.id.as_str()))
+            .cloned()
+    }
+
+    /// Number of distinct resources in the index
+    pub fn len(&self) -> usize {
+        self.resources.values().map(HashMap::len).sum()
+    }
+
+    /// Returns `true` if the index contains no resources
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
     }
 }
 
@@ -274,7 +568,7 @@ pub fn vec_to_jsonapi_resources<T: JsonApiModel>(
 }
 
 /// Converts a `vec!` of structs into a
-/// [`JsonApiDocument`](../api/struct.JsonApiDocument.html)
+/// [`JsonApiDocument`](../api/type.JsonApiDocument.html)
 ///
 /// ```rust
 /// #[macro_use] extern crate serde_derive;
@@ -314,9 +608,58 @@ pub fn vec_to_jsonapi_document<T: JsonApiModel>(objects: Vec<T>) -> JsonApiDocum
     )
 }
 
+/// Like [`vec_to_jsonapi_document`](fn.vec_to_jsonapi_document.html),
+/// converting the structs according to `ctx` and returning an error when the
+/// conversion of one of them fails
+pub fn vec_to_jsonapi_document_with<T: JsonApiModel>(objects: &[T], ctx: &ConversionContext)
+    -> Result<JsonApiDocument>
+{
+    let (resources, included) = vec_to_jsonapi_resources_with(objects, ctx)?;
+    Ok(JsonApiDocument::Data (
+        DocumentData {
+            data: Some(PrimaryData::Multiple(resources)),
+            included,
+            ..Default::default()
+        }
+    ))
+}
+
+/// Converts the structs into their resources and the resources to be included
+/// alongside them, according to `ctx`
+pub(crate) fn vec_to_jsonapi_resources_with<T: JsonApiModel>(objects: &[T], ctx: &ConversionContext)
+    -> Result<(Resources, Option<Resources>)>
+{
+    let mut resources = Vec::with_capacity(objects.len());
+    let mut included = vec![];
+    for obj in objects {
+        let (res, opt_incl) = obj.try_to_jsonapi_resource_with(ctx)?;
+        resources.push(res);
+        included.extend(opt_incl.into_iter().flatten());
+    }
+    Ok((resources, if included.is_empty() { None } else { Some(included) }))
+}
+
+/// Converts the primary data of a document into a `Vec` of structs, reading
+/// member names and checking the resources according to `ctx`. A document with
+/// a single primary resource results in a `Vec` with one element. The
+/// `included` resources are indexed once for the whole document.
+pub(crate) fn vec_from_jsonapi_document_with<T: JsonApiModel>(doc: &GenericDocumentData<impl Sized, Meta>, ctx: &ConversionContext)
+    -> Result<Vec<T>>
+{
+    let resources: Vec<(String, &Resource)> = match doc.data {
+        Some(PrimaryData::Single(ref resource)) => vec![("/data".into(), resource)],
+        Some(PrimaryData::Multiple(ref resources)) => {
+            resources.iter().enumerate().map(|(i, resource)| (format!("/data/{}", i), resource)).collect()
+        }
+        Some(PrimaryData::None) | None => return Err(ConversionError::NoData.into()),
+    };
+    let index = doc.included.as_ref().map(|inc| IncludedIndex::new(inc));
+    Vec::<T>::deserialize(de::resources(resources, index.as_ref(), ctx, T::descriptor())).map_err(Error::from)
+}
+
 impl<M: JsonApiModel> JsonApiModel for Box<M> {
-    fn jsonapi_type(&self) -> String {
-        self.as_ref().jsonapi_type()
+    fn jsonapi_type_name() -> &'static str {
+        M::jsonapi_type_name()
     }
 
     fn jsonapi_id(&self) -> String {
@@ -331,47 +674,139 @@ impl<M: JsonApiModel> JsonApiModel for Box<M> {
         self.as_ref().build_relationships()
     }
 
-    fn build_included(&self) -> Option<Resources> {
-        self.as_ref().build_included()
+    fn build_included(&self, ctx: &ConversionContext) -> Result<Option<Resources>> {
+        self.as_ref().build_included(ctx)
+    }
+
+    fn naming_policy() -> Option<NamingPolicy> {
+        M::naming_policy()
+    }
+
+    fn link_templates() -> Option<LinkTemplates> {
+        M::link_templates()
+    }
+
+    fn jsonapi_links(&self, ctx: &ConversionContext) -> Option<Links> {
+        self.as_ref().jsonapi_links(ctx)
+    }
+
+    fn jsonapi_relationship_links(&self, name: &str, ctx: &ConversionContext) -> Option<Links> {
+        self.as_ref().jsonapi_relationship_links(name, ctx)
+    }
+
+    fn jsonapi_meta(&self) -> Option<Meta> {
+        self.as_ref().jsonapi_meta()
+    }
+
+    fn jsonapi_id_value(id: &str) -> Result<Value> {
+        M::jsonapi_id_value(id)
+    }
+
+    fn relationship_descriptor(name: &str) -> Option<ModelDescriptor> {
+        M::relationship_descriptor(name)
     }
 }
 
 /// When applied this macro implements the
 /// [`JsonApiModel`](model/trait.JsonApiModel.html) trait for the provided type
 ///
+/// The resource type is a `&'static str`, a string literal or a `const`.
+/// Relationships are listed after `has one` and `has many`. A relationship
+/// field renamed with `#[serde(rename = "...")]` names its serde name with
+/// `as`, and an optional trailing block holds further items of the
+/// implementation, such as a [`naming_policy`](model/trait.JsonApiModel.html#method.naming_policy):
+///
+/// ```rust
+/// #[macro_use] extern crate serde_derive;
+/// #[macro_use] extern crate jsonapi;
+/// use jsonapi::model::*;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Person {
+///     id: String,
+///     name: String,
+/// }
+/// jsonapi_model!(Person; "people");
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Article {
+///     id: String,
+///     page_count: u32,
+///     #[serde(rename = "writer")]
+///     author: Person,
+/// }
+/// jsonapi_model!(Article; "articles"; has one author as "writer"; {
+///     fn naming_policy() -> Option<NamingPolicy> {
+///         Some(NamingPolicy::KebabCase)
+///     }
+/// });
+///
+/// let article = Article {
+///     id: "1".into(),
+///     page_count: 12,
+///     author: Person { id: "2".into(), name: "Ada".into() },
+/// };
+/// let (resource, included) = article.to_jsonapi_resource();
+/// assert!(resource.attributes.contains_key("page-count"));
+/// assert!(resource.relationships.as_ref().unwrap().contains_key("writer"));
+/// assert_eq!(Article::from_jsonapi_resource(&resource, &included).unwrap(), article);
+/// ```
 #[macro_export]
 macro_rules! jsonapi_model {
-    ($model:ty; $type:expr) => (
+    ($model:ty; $type:expr $(; { $($extra:tt)* })?) => (
         impl JsonApiModel for $model {
-            fn jsonapi_type(&self) -> String { $type.to_string() }
-            fn jsonapi_id(&self) -> String { self.id.to_string() }
+            fn jsonapi_type_name() -> &'static str { $type }
+            fn jsonapi_id(&self) -> String {
+                $crate::id::JsonApiIdType::to_jsonapi_id(&self.id)
+            }
             fn relationship_fields() -> Option<&'static [&'static str]> { None }
             fn build_relationships(&self) -> Option<Relationships> { None }
-            fn build_included(&self) -> Option<Resources> { None }
+            fn build_included(&self, _ctx: &$crate::model::ConversionContext)
+                -> $crate::errors::Result<Option<Resources>>
+            {
+                Ok(None)
+            }
+            fn jsonapi_id_value(id: &str) -> $crate::errors::Result<$crate::api::JsonApiValue> {
+                $crate::model::id_value_of(|model: &Self| &model.id, id)
+            }
+            $($($extra)*)?
         }
     );
     ($model:ty; $type:expr;
-        has one $( $has_one:ident ),*
+        has one $( $has_one:ident $(as $one_name:literal)? ),*
+        $(; { $($extra:tt)* })?
     ) => (
-        jsonapi_model!($model; $type; has one $( $has_one ),*; has many);
+        jsonapi_model!($model; $type;
+            has one $( $has_one $(as $one_name)? ),*;
+            has many
+            $(; { $($extra)* })?
+        );
     );
     ($model:ty; $type:expr;
-        has many $( $has_many:ident ),*
+        has many $( $has_many:ident $(as $many_name:literal)? ),*
+        $(; { $($extra:tt)* })?
     ) => (
-        jsonapi_model!($model; $type; has one; has many $( $has_many ),*);
+        jsonapi_model!($model; $type;
+            has one;
+            has many $( $has_many $(as $many_name)? ),*
+            $(; { $($extra)* })?
+        );
     );
     ($model:ty; $type:expr;
-        has one $( $has_one:ident ),*;
-        has many $( $has_many:ident ),*
+        has one $( $has_one:ident $(as $one_name:literal)? ),*;
+        has many $( $has_many:ident $(as $many_name:literal)? ),*
+        $(; { $($extra:tt)* })?
     ) => (
         impl JsonApiModel for $model {
-            fn jsonapi_type(&self) -> String { $type.to_string() }
-            fn jsonapi_id(&self) -> String { self.id.to_string() }
+            fn jsonapi_type_name() -> &'static str { $type }
+            fn jsonapi_id(&self) -> String {
+                $crate::id::JsonApiIdType::to_jsonapi_id(&self.id)
+            }
 
             fn relationship_fields() -> Option<&'static [&'static str]> {
                 static FIELDS: &'static [&'static str] = &[
-                     $( stringify!($has_one),)*
-                     $( stringify!($has_many),)*
+                     $( $crate::__jsonapi_field_name!($has_one $(as $one_name)?),)*
+                     $( $crate::__jsonapi_field_name!($has_many $(as $many_name)?),)*
                 ];
 
                 Some(FIELDS)
@@ -380,32 +815,64 @@ macro_rules! jsonapi_model {
             fn build_relationships(&self) -> Option<Relationships> {
                 let mut relationships = HashMap::new();
                 $(
-                    relationships.insert(stringify!($has_one).into(),
+                    relationships.insert(
+                        $crate::__jsonapi_field_name!($has_one $(as $one_name)?).into(),
                         Self::build_has_one(&self.$has_one)
                     );
                 )*
                 $(
                     relationships.insert(
-                        stringify!($has_many).into(),
-                        {
-                            let values = &self.$has_many.get_models();
-                            Self::build_has_many(values)
-                        }
+                        $crate::__jsonapi_field_name!($has_many $(as $many_name)?).into(),
+                        Self::build_has_many($crate::array::JsonApiArray::get_models(&self.$has_many))
                     );
                 )*
                 Some(relationships)
             }
 
-            fn build_included(&self) -> Option<Resources> {
+            fn build_included(&self, ctx: &$crate::model::ConversionContext)
+                -> $crate::errors::Result<Option<Resources>>
+            {
                 let mut included:Resources = vec![];
-                $( included.append(&mut self.$has_one.to_resources()); )*
                 $(
-                    for model in self.$has_many.get_models() {
-                        included.append(&mut model.to_resources());
+                    included.append(
+                        &mut $crate::related::JsonApiRelated::related_resources(&self.$has_one, ctx)?
+                    );
+                )*
+                $(
+                    for model in $crate::array::JsonApiArray::get_models(&self.$has_many) {
+                        included.append(&mut $crate::related::JsonApiRelated::related_resources(model, ctx)?);
                     }
                 )*
-                Some(included)
+                Ok(Some(included))
+            }
+
+            fn jsonapi_id_value(id: &str) -> $crate::errors::Result<$crate::api::JsonApiValue> {
+                $crate::model::id_value_of(|model: &Self| &model.id, id)
             }
+
+            fn relationship_descriptor(name: &str) -> Option<$crate::model::ModelDescriptor> {
+                $(
+                    if name == $crate::__jsonapi_field_name!($has_one $(as $one_name)?) {
+                        return Some($crate::model::descriptor_of_one(|model: &Self| &model.$has_one));
+                    }
+                )*
+                $(
+                    if name == $crate::__jsonapi_field_name!($has_many $(as $many_name)?) {
+                        return Some($crate::model::descriptor_of_many(|model: &Self| &model.$has_many));
+                    }
+                )*
+                None
+            }
+
+            $($($extra)*)?
         }
     );
 }
+
+/// The name serde uses for a relationship field of `jsonapi_model!`
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __jsonapi_field_name {
+    ($field:ident) => (stringify!($field));
+    ($field:ident as $name:literal) => ($name);
+}
@@ -0,0 +1,99 @@
+//! Defines the `NamingPolicy` that decides how the fields of a model are named
+//! as members of a resource.
+//!
+//! A policy applies to the names serde uses for the fields of a model, so it
+//! composes with `#[serde(rename = "...")]`: the renamed field is converted like
+//! any other. The same policy names attributes, relationships and the members
+//! listed in sparse fieldsets.
+//!
+//! ```rust
+//! use jsonapi::naming::NamingPolicy;
+//!
+//! assert_eq!(NamingPolicy::CamelCase.apply("first_name"), "firstName");
+//! assert_eq!(NamingPolicy::KebabCase.apply("first_name"), "first-name");
+//! assert_eq!(NamingPolicy::SnakeCase.apply("firstName"), "first_name");
+//! assert_eq!(NamingPolicy::KebabCase.apply("HTTPServer"), "http-server");
+//! assert_eq!(NamingPolicy::AsIs.apply("first_name"), "first_name");
+//! ```
+
+/// How the field names of a model are converted into member names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NamingPolicy {
+    /// Use the field names serde produces unchanged
+    #[default]
+    AsIs,
+    /// `first_name` becomes `firstName`
+    CamelCase,
+    /// `first_name` becomes `FirstName`
+    PascalCase,
+    /// `first_name` becomes `first-name`
+    KebabCase,
+    /// `firstName` becomes `first_name`
+    SnakeCase,
+}
+
+impl NamingPolicy {
+    /// The member name for the field named `name`
+    pub fn apply(&self, name: &str) -> String {
+        match *self {
+            NamingPolicy::AsIs => name.to_string(),
+            NamingPolicy::CamelCase => words(name)
+                .iter()
+                .enumerate()
+                .map(|(i, word)| if i == 0 { word.to_lowercase() } else { capitalize(word) })
+                .collect(),
+            NamingPolicy::PascalCase => words(name).iter().map(|word| capitalize(word)).collect(),
+            NamingPolicy::KebabCase => lowercase_joined(name, "-"),
+            NamingPolicy::SnakeCase => lowercase_joined(name, "_"),
+        }
+    }
+}
+
+/// Split `name` into words at `_`, `-` and changes from lower to upper case.
+/// A run of capitals is one word, so `HTTPServer` splits into `HTTP` and
+/// `Server`.
+fn words(name: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = name.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, &(at, c)) in chars.iter().enumerate() {
+        if c == '_' || c == '-' {
+            if let Some(begin) = start.take() {
+                words.push(&name[begin..at]);
+            }
+            continue;
+        }
+        if let Some(begin) = start {
+            let prev = chars[i - 1].1;
+            let next_is_lower = chars.get(i + 1).is_some_and(|&(_, n)| n.is_lowercase());
+            let boundary = c.is_uppercase()
+                && (prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower));
+            if boundary {
+                words.push(&name[begin..at]);
+                start = Some(at);
+            }
+        } else {
+            start = Some(at);
+        }
+    }
+    if let Some(begin) = start {
+        words.push(&name[begin..]);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+        None => String::new(),
+    }
+}
+
+fn lowercase_joined(name: &str, separator: &str) -> String {
+    words(name)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
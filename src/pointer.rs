@@ -0,0 +1,105 @@
+//! JSON pointers ([RFC 6901](https://tools.ietf.org/html/rfc6901)) into
+//! documents: built for the members that fail to deserialize, and resolved to
+//! check the pointers of error sources
+use crate::api::{JsonApiValue, PointerValidationError};
+use serde_path_to_error::{Path, Segment};
+
+/// Escapes a member name for use as a reference token
+pub(crate) fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Appends the members of `path` to `pointer`. Segments serde cannot name end
+/// the pointer, which then points at the closest member that is known.
+pub(crate) fn push_path(pointer: &mut String, path: &Path) {
+    for segment in path.iter() {
+        match *segment {
+            Segment::Seq { index } => {
+                pointer.push('/');
+                pointer.push_str(&index.to_string());
+            }
+            Segment::Map { ref key } => {
+                pointer.push('/');
+                pointer.push_str(&escape(key));
+            }
+            Segment::Enum { ref variant } => {
+                pointer.push('/');
+                pointer.push_str(&escape(variant));
+            }
+            Segment::Unknown => break,
+        }
+    }
+}
+
+/// The pointer to the member `path` leads to
+pub(crate) fn from_path(path: &Path) -> String {
+    let mut pointer = String::new();
+    push_path(&mut pointer, path);
+    pointer
+}
+
+/// The reference tokens of `pointer`, unescaped
+pub(crate) fn tokens(pointer: &str) -> Result<Vec<String>, PointerValidationError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return Err(PointerValidationError::InvalidSyntax);
+    }
+    pointer[1..].split('/').map(unescape).collect()
+}
+
+fn unescape(token: &str) -> Result<String, PointerValidationError> {
+    let mut unescaped = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next() {
+                Some('0') => unescaped.push('~'),
+                Some('1') => unescaped.push('/'),
+                _ => return Err(PointerValidationError::InvalidSyntax),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    Ok(unescaped)
+}
+
+/// The array index `token` refers to. Indices must be written without
+/// leading zeros.
+pub(crate) fn index(token: &str) -> Option<usize> {
+    let is_index = token == "0" || (!token.starts_with('0') && token.bytes().all(|b| b.is_ascii_digit()));
+    if is_index {
+        token.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// The member or element `token` refers to in `value`
+pub(crate) fn child_mut<'a>(value: &'a mut JsonApiValue, token: &str) -> Option<&'a mut JsonApiValue> {
+    match *value {
+        JsonApiValue::Object(ref mut members) => members.get_mut(token),
+        JsonApiValue::Array(ref mut elements) => index(token).and_then(move |index| elements.get_mut(index)),
+        _ => None,
+    }
+}
+
+/// The value `pointer` refers to in `document`, `None` when there is none
+pub(crate) fn resolve<'a>(document: &'a JsonApiValue, pointer: &str)
+    -> Result<Option<&'a JsonApiValue>, PointerValidationError>
+{
+    let mut value = document;
+    for token in tokens(pointer)? {
+        let next = match *value {
+            JsonApiValue::Object(ref members) => members.get(&token),
+            JsonApiValue::Array(ref elements) => index(&token).and_then(|index| elements.get(index)),
+            _ => None,
+        };
+        match next {
+            Some(next) => value = next,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(value))
+}
@@ -1,3 +1,4 @@
+use crate::errors::*;
 use queryst::parse;
 use std::collections::HashMap;
 use serde_json::value::Value;
@@ -220,6 +221,36 @@ impl Query {
         }
     }
 
+    ///
+    /// Like [`from_params`](#method.from_params), failing on a query string
+    /// that cannot be parsed or a `page` parameter that is not an integer
+    /// instead of falling back to defaults
+    ///
+    /// ```
+    /// use jsonapi::errors::{Error, QueryError};
+    /// use jsonapi::query::Query;
+    ///
+    /// let query = Query::try_from_params("page[number]=3&page[size]=10").unwrap();
+    /// assert_eq!(query.page.unwrap().number, 3);
+    ///
+    /// match Query::try_from_params("page[number]=three") {
+    ///     Err(Error::Query(QueryError::InvalidPage(ref parameter, _))) => assert_eq!(parameter, "page[number]"),
+    ///     _ => assert!(false),
+    /// }
+    /// ```
+    pub fn try_from_params(params: &str) -> Result<Self> {
+        let o = parse(params).map_err(|err| QueryError::Syntax(err.message))?;
+        for &(parameter, pointer) in &[("page[number]", "/page/number"), ("page[size]", "/page/size")] {
+            if let Some(value) = o.pointer(pointer) {
+                if value.as_str().and_then(|v| v.parse::<i64>().ok()).is_none() {
+                    let value = value.as_str().map_or_else(|| value.to_string(), String::from);
+                    return Err(QueryError::InvalidPage(parameter.into(), value).into());
+                }
+            }
+        }
+        Ok(ok_params(o))
+    }
+
     ///
     /// Builds a query parameter string from a Query
     ///
@@ -0,0 +1,218 @@
+//! Defines the `JsonApiRelated` trait for the values held by the relationship
+//! fields of a model, and the [`jsonapi_polymorphic!`](../macro.jsonapi_polymorphic.html)
+//! macro for relationships that point at resources of more than one type.
+//!
+//! Every [`JsonApiModel`](../model/trait.JsonApiModel.html) can be related to,
+//! as can identifiers, `Option`s of related values for optional to-one
+//! relationships, and related values behind an `Rc` or `Arc` (with serde's
+//! `rc` feature). `Box`ed models are models themselves.
+//! A polymorphic relationship is an enum with one newtype variant per model,
+//! using serde's default enum representation:
+//!
+//! ```rust
+//! #[macro_use] extern crate serde_derive;
+//! #[macro_use] extern crate jsonapi;
+//! use jsonapi::model::*;
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Article {
+//!     id: String,
+//!     title: String,
+//! }
+//! jsonapi_model!(Article; "articles");
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Video {
+//!     id: String,
+//!     url: String,
+//! }
+//! jsonapi_model!(Video; "videos");
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! enum Commentable {
+//!     Article(Article),
+//!     Video(Video),
+//! }
+//! jsonapi_polymorphic!(Commentable; "articles" => Article, "videos" => Video);
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Comment {
+//!     id: String,
+//!     body: String,
+//!     commentable: Commentable,
+//! }
+//! jsonapi_model!(Comment; "comments"; has one commentable);
+//!
+//! let comment = Comment {
+//!     id: "1".into(),
+//!     body: "Nice".into(),
+//!     commentable: Commentable::Video(Video { id: "2".into(), url: "/v/2".into() }),
+//! };
+//! let (resource, included) = comment.to_jsonapi_resource();
+//! assert_eq!(included.as_ref().unwrap()[0]._type, "videos");
+//! assert_eq!(Comment::from_jsonapi_resource(&resource, &included).unwrap(), comment);
+//! ```
+use crate::api::*;
+use crate::errors::*;
+use crate::id::Id;
+use crate::model::{ConversionContext, JsonApiModel, ModelDescriptor};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A value that a relationship of a model can hold
+pub trait JsonApiRelated {
+    /// The identifier of the resource the value stands for, `None` when it
+    /// stands for no resource
+    fn related_identifier(&self) -> Option<ResourceIdentifier>;
+    /// The resources to include alongside the resource holding the value
+    fn related_resources(&self, ctx: &ConversionContext) -> Result<Resources>;
+    /// Describes how the value is deserialized from a related resource
+    #[doc(hidden)]
+    fn related_descriptor() -> ModelDescriptor;
+}
+
+impl<M: JsonApiModel> JsonApiRelated for M {
+    fn related_identifier(&self) -> Option<ResourceIdentifier> {
+        Some(self.as_resource_identifier())
+    }
+
+    fn related_resources(&self, ctx: &ConversionContext) -> Result<Resources> {
+        self.to_resources(ctx)
+    }
+
+    fn related_descriptor() -> ModelDescriptor {
+        M::descriptor()
+    }
+}
+
+/// Linkage only: nothing is included, and the value deserializes from the
+/// identifier even when the related resource is not included
+impl JsonApiRelated for ResourceIdentifier {
+    fn related_identifier(&self) -> Option<ResourceIdentifier> {
+        Some(self.clone())
+    }
+
+    fn related_resources(&self, _ctx: &ConversionContext) -> Result<Resources> {
+        Ok(Vec::new())
+    }
+
+    fn related_descriptor() -> ModelDescriptor {
+        ModelDescriptor {
+            linkage: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Linkage only, like `ResourceIdentifier`, with the `type` of the model `T`
+/// and an `id` checked to be valid for it
+impl<T: JsonApiModel> JsonApiRelated for Id<T> {
+    fn related_identifier(&self) -> Option<ResourceIdentifier> {
+        Some(ResourceIdentifier {
+            _type: self.type_name().to_string(),
+            id: self.id.clone(),
+        })
+    }
+
+    fn related_resources(&self, _ctx: &ConversionContext) -> Result<Resources> {
+        Ok(Vec::new())
+    }
+
+    fn related_descriptor() -> ModelDescriptor {
+        ModelDescriptor {
+            id_value: |id| T::jsonapi_id_value(id).map(|_| JsonApiValue::String(id.to_string())),
+            linkage: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// An optional to-one relationship: `None` is serialized as `"data": null`,
+/// and a relationship whose resource is missing or not included deserializes
+/// as `None`
+impl<R: JsonApiRelated> JsonApiRelated for Option<R> {
+    fn related_identifier(&self) -> Option<ResourceIdentifier> {
+        self.as_ref().and_then(R::related_identifier)
+    }
+
+    fn related_resources(&self, ctx: &ConversionContext) -> Result<Resources> {
+        match *self {
+            Some(ref related) => related.related_resources(ctx),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn related_descriptor() -> ModelDescriptor {
+        R::related_descriptor()
+    }
+}
+
+macro_rules! impl_related_pointer {
+    ($($pointer:ident),*) => {
+        $(
+            impl<R: JsonApiRelated> JsonApiRelated for $pointer<R> {
+                fn related_identifier(&self) -> Option<ResourceIdentifier> {
+                    self.as_ref().related_identifier()
+                }
+
+                fn related_resources(&self, ctx: &ConversionContext) -> Result<Resources> {
+                    self.as_ref().related_resources(ctx)
+                }
+
+                fn related_descriptor() -> ModelDescriptor {
+                    R::related_descriptor()
+                }
+            }
+        )*
+    };
+}
+
+impl_related_pointer!(Rc, Arc);
+
+/// The descriptor of the value held by the variant `constructor` builds
+#[doc(hidden)]
+pub fn descriptor_of_variant<E, R: JsonApiRelated>(_constructor: fn(R) -> E) -> ModelDescriptor {
+    R::related_descriptor()
+}
+
+/// Implements [`JsonApiRelated`](related/trait.JsonApiRelated.html) for an enum
+/// whose newtype variants each hold a model, mapping resource types to
+/// variants. See the [`related`](related/index.html) module for an example.
+#[macro_export]
+macro_rules! jsonapi_polymorphic {
+    ($enum:ident; $( $type:expr => $variant:ident ),+ $(,)?) => (
+        impl $crate::related::JsonApiRelated for $enum {
+            fn related_identifier(&self) -> Option<$crate::api::ResourceIdentifier> {
+                match *self {
+                    $( $enum::$variant(ref model) => $crate::related::JsonApiRelated::related_identifier(model), )+
+                }
+            }
+
+            fn related_resources(&self, ctx: &$crate::model::ConversionContext)
+                -> $crate::errors::Result<$crate::api::Resources>
+            {
+                match *self {
+                    $( $enum::$variant(ref model) => $crate::related::JsonApiRelated::related_resources(model, ctx), )+
+                }
+            }
+
+            fn related_descriptor() -> $crate::model::ModelDescriptor {
+                fn variant(_type: &str) -> Option<(&'static str, $crate::model::ModelDescriptor)> {
+                    $(
+                        if _type == $type {
+                            return Some((
+                                stringify!($variant),
+                                $crate::related::descriptor_of_variant($enum::$variant),
+                            ));
+                        }
+                    )+
+                    None
+                }
+                $crate::model::ModelDescriptor {
+                    variant,
+                    ..Default::default()
+                }
+            }
+        }
+    );
+}
@@ -0,0 +1,255 @@
+//! A `serde::Serializer` that writes the attributes of a model straight into
+//! [`ResourceAttributes`](../api/type.ResourceAttributes.html).
+//!
+//! The `id` member and any relationship fields are skipped rather than being
+//! serialized and discarded afterwards, and each remaining field is converted
+//! to a `JsonApiValue` exactly once.
+use crate::api::*;
+use crate::errors::{ConversionError, Error as JsonApiError};
+use crate::naming::NamingPolicy;
+use serde::ser::{self, Impossible, Serialize};
+use serde_json::to_value;
+use std::fmt;
+
+/// Serialize `value` into the attributes of a resource, leaving out `id` and
+/// the fields named in `relationship_fields`. Attributes are named by applying
+/// `naming` to the field names.
+///
+/// A struct must serialize every field of `relationship_fields`, or skip it,
+/// under that name: a relationship field renamed by serde would otherwise end
+/// up in the attributes.
+pub(crate) fn to_attributes<T: Serialize + ?Sized>(
+    value: &T,
+    relationship_fields: Option<&'static [&'static str]>,
+    naming: NamingPolicy,
+) -> Result<ResourceAttributes, Error> {
+    value.serialize(AttributesSerializer { relationship_fields, naming })
+}
+
+/// The reasons a model can fail to serialize into attributes
+#[derive(Debug)]
+pub(crate) enum Error {
+    /// The model serialized to something other than a struct or map
+    NotAnObject(&'static str),
+    /// The value of the named field could not be serialized
+    Attribute(String, serde_json::Error),
+    /// The named relationship field is not a field of the struct as
+    /// serialized, most likely because serde renames it
+    MissingRelationship(&'static str),
+    /// The `Serialize` implementation of the model reported an error
+    Custom(String),
+}
+
+impl Error {
+    /// Describe the failure as an error of the `model` named
+    pub(crate) fn for_model(self, model: String) -> JsonApiError {
+        match self {
+            Error::Attribute(field, err) => ConversionError::AttributeSerialization(model, field, err).into(),
+            err => ConversionError::ModelSerialization(model, Box::new(err)).into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::NotAnObject(found) => write!(f, "expected a struct or map, found {}", found),
+            Error::Attribute(ref field, ref err) => write!(f, "attribute '{}': {}", field, err),
+            Error::MissingRelationship(field) => write!(
+                f,
+                "relationship '{}' is not a serialized field, name a renamed field with `as` in `jsonapi_model!`",
+                field
+            ),
+            Error::Custom(ref msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
+
+struct AttributesSerializer {
+    relationship_fields: Option<&'static [&'static str]>,
+    naming: NamingPolicy,
+}
+
+impl AttributesSerializer {
+    fn is_attribute(&self, key: &str) -> bool {
+        key != "id" && !self.is_relationship(key)
+    }
+
+    fn is_relationship(&self, key: &str) -> bool {
+        self.relationship_fields.is_some_and(|fields| fields.contains(&key))
+    }
+}
+
+fn not_an_object<T>(found: &'static str) -> Result<T, Error> {
+    Err(Error::NotAnObject(found))
+}
+
+macro_rules! reject {
+    ($($method:ident($($arg:ty),*) -> $ret:ty, $found:expr;)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<$ret, Error> {
+                not_an_object($found)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for AttributesSerializer {
+    type Ok = ResourceAttributes;
+    type Error = Error;
+    type SerializeSeq = Impossible<ResourceAttributes, Error>;
+    type SerializeTuple = Impossible<ResourceAttributes, Error>;
+    type SerializeTupleStruct = Impossible<ResourceAttributes, Error>;
+    type SerializeTupleVariant = Impossible<ResourceAttributes, Error>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = StructSerializer;
+    type SerializeStructVariant = Impossible<ResourceAttributes, Error>;
+
+    reject! {
+        serialize_bool(bool) -> ResourceAttributes, "a boolean";
+        serialize_i8(i8) -> ResourceAttributes, "an integer";
+        serialize_i16(i16) -> ResourceAttributes, "an integer";
+        serialize_i32(i32) -> ResourceAttributes, "an integer";
+        serialize_i64(i64) -> ResourceAttributes, "an integer";
+        serialize_u8(u8) -> ResourceAttributes, "an integer";
+        serialize_u16(u16) -> ResourceAttributes, "an integer";
+        serialize_u32(u32) -> ResourceAttributes, "an integer";
+        serialize_u64(u64) -> ResourceAttributes, "an integer";
+        serialize_f32(f32) -> ResourceAttributes, "a float";
+        serialize_f64(f64) -> ResourceAttributes, "a float";
+        serialize_char(char) -> ResourceAttributes, "a char";
+        serialize_str(&str) -> ResourceAttributes, "a string";
+        serialize_bytes(&[u8]) -> ResourceAttributes, "bytes";
+        serialize_none() -> ResourceAttributes, "none";
+        serialize_unit() -> ResourceAttributes, "unit";
+        serialize_unit_struct(&'static str) -> ResourceAttributes, "a unit struct";
+        serialize_unit_variant(&'static str, u32, &'static str) -> ResourceAttributes, "an enum";
+        serialize_seq(Option<usize>) -> Self::SerializeSeq, "a sequence";
+        serialize_tuple(usize) -> Self::SerializeTuple, "a tuple";
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct, "a tuple struct";
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant, "an enum";
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant, "an enum";
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Error> {
+        not_an_object("an enum")
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Ok(MapSerializer {
+            serializer: self,
+            attributes: ResourceAttributes::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct, Error> {
+        Ok(StructSerializer {
+            serializer: self,
+            attributes: ResourceAttributes::with_capacity(len),
+            relationships: Vec::new(),
+        })
+    }
+}
+
+struct StructSerializer {
+    serializer: AttributesSerializer,
+    attributes: ResourceAttributes,
+    /// The relationship fields serialized or skipped so far
+    relationships: Vec<&'static str>,
+}
+
+impl ser::SerializeStruct for StructSerializer {
+    type Ok = ResourceAttributes;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        if self.serializer.is_attribute(key) {
+            let value = to_value(value).map_err(|err| Error::Attribute(key.to_string(), err))?;
+            self.attributes.insert(self.serializer.naming.apply(key), value);
+        } else if self.serializer.is_relationship(key) {
+            self.relationships.push(key);
+        }
+        Ok(())
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Error> {
+        if self.serializer.is_relationship(key) {
+            self.relationships.push(key);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<ResourceAttributes, Error> {
+        let fields = self.serializer.relationship_fields.unwrap_or(&[]);
+        match fields.iter().find(|field| !self.relationships.contains(field)) {
+            Some(field) => Err(Error::MissingRelationship(field)),
+            None => Ok(self.attributes),
+        }
+    }
+}
+
+struct MapSerializer {
+    serializer: AttributesSerializer,
+    attributes: ResourceAttributes,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = ResourceAttributes;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        match to_value(key) {
+            Ok(JsonApiValue::String(key)) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(Error::Custom("attribute names must be strings".into())),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::Custom("serialize_value called before serialize_key".into()))?;
+        if self.serializer.is_attribute(&key) {
+            match to_value(value) {
+                Ok(value) => self.attributes.insert(self.serializer.naming.apply(&key), value),
+                Err(err) => return Err(Error::Attribute(key, err)),
+            };
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<ResourceAttributes, Error> {
+        Ok(self.attributes)
+    }
+}
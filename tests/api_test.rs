@@ -1,5 +1,6 @@
 //! The purpose of these tests is to validate compliance with the JSONAPI
 //! specification and to ensure that this crate reads documents properly
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
 extern crate jsonapi;
 extern crate serde_json;
 extern crate env_logger;
@@ -457,7 +458,7 @@ fn can_diff_resource() {
                         }
                         Ok(patchset) => {
                             println!("can_diff_resource: PatchSet is {:?}", patchset);
-                            assert_eq!(patchset.patches.len(), 5);
+                            assert_eq!(patchset.patches.len(), 7);
                         }
                     }
                 }
@@ -610,3 +611,801 @@ fn it_validates_partialeq_when_compariing_documents() {
     assert_eq!(doc2.is_ok(), true);
     assert!(doc1.unwrap() == doc2.unwrap());
 }
+
+#[test]
+fn it_builds_errors() {
+    let error = JsonApiError::new(422)
+        .id("1")
+        .code("invalid")
+        .detail("Email is not an email address")
+        .pointer("/data/attributes/email")
+        .link("about", "/errors/invalid")
+        .meta("attempts", 3);
+
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        serde_json::json!({
+            "id": "1",
+            "links": {"about": "/errors/invalid"},
+            "status": "422",
+            "code": "invalid",
+            "title": "Unprocessable Entity",
+            "detail": "Email is not an email address",
+            "source": {"pointer": "/data/attributes/email"},
+            "meta": {"attempts": 3}
+        })
+    );
+    assert_eq!(error.status_code(), Some(422));
+
+    let error = JsonApiError::new(499).title("Client Closed Request").parameter("include");
+    assert_eq!(error.title, Some("Client Closed Request".into()));
+    assert_eq!(error.source.unwrap().parameter, Some("include".into()));
+}
+
+#[test]
+fn it_has_standard_errors() {
+    assert_eq!(JsonApiError::not_found().status, Some("404".into()));
+    assert_eq!(JsonApiError::not_found().title, Some("Not Found".into()));
+    assert_eq!(JsonApiError::conflict().status_code(), Some(409));
+    assert_eq!(JsonApiError::unsupported_media_type().title, Some("Unsupported Media Type".into()));
+    assert_eq!(JsonApiError::internal_server_error().status_code(), Some(500));
+}
+
+#[test]
+fn it_picks_the_most_general_status() {
+    let doc = DocumentError::from_errors(vec![JsonApiError::conflict(), JsonApiError::conflict()]);
+    assert_eq!(doc.status(), Some(409));
+
+    let doc = DocumentError::from_errors(vec![JsonApiError::conflict(), JsonApiError::unprocessable_entity()]);
+    assert_eq!(doc.status(), Some(400));
+
+    let doc = DocumentError::from_errors(vec![JsonApiError::not_found(), JsonApiError::service_unavailable()]);
+    assert_eq!(doc.status(), Some(500));
+
+    let doc = DocumentError::from_errors(vec![JsonApiError::default()]);
+    assert_eq!(doc.status(), None);
+    assert_eq!(doc.errors.len(), 1);
+}
+
+#[test]
+fn it_converts_crate_errors() {
+    use jsonapi::errors::{ConversionError, Error, QueryError};
+
+    let err: Error = ConversionError::ResourceTypeMismatch("people/9".into(), "posts".into()).into();
+    let error = JsonApiError::from(&err);
+    assert_eq!(error.status_code(), Some(409));
+    assert_eq!(error.code, Some("type_mismatch".into()));
+    assert_eq!(error.detail, Some("Resource 'people/9' is not of type 'posts'".into()));
+
+    let err: Error = ConversionError::MissingAttribute("posts/1".into(), "title".into()).into();
+    assert_eq!(JsonApiError::from(err).status_code(), Some(422));
+
+    let err = <JsonApiDocument as std::str::FromStr>::from_str("{").unwrap_err();
+    assert_eq!(JsonApiError::from(err).status_code(), Some(400));
+
+    let err: Error = ConversionError::ModelSerialization("posts".into(), "key must be a string".into()).into();
+    assert_eq!(JsonApiError::from(err).status_code(), Some(500));
+
+    let err: Error = DiffPatchError::IncompatibleTypes("posts".into(), "people".into()).into();
+    assert_eq!(JsonApiError::from(err).status_code(), Some(409));
+
+    let err: Error = QueryError::InvalidPage("page[size]".into(), "ten".into()).into();
+    let error = JsonApiError::from(err);
+    assert_eq!(error.status_code(), Some(400));
+    assert_eq!(error.source.unwrap().parameter, Some("page[size]".into()));
+
+    let err: Error = DocumentValidationError::MissingContent.into();
+    assert_eq!(JsonApiError::from(err).code, Some("missing_content".into()));
+
+    let err: Error = serde_json::from_str::<JsonApiDocument>("{").unwrap_err().into();
+    assert_eq!(JsonApiError::from(err).status_code(), Some(400));
+
+    let doc = DocumentError::from_errors(vec![
+        DocumentValidationError::IncludedWithoutData,
+        DocumentValidationError::MissingContent,
+    ]);
+    assert_eq!(doc.status(), Some(400));
+    assert_eq!(doc.errors[0].source.as_ref().unwrap().pointer, Some("/included".into()));
+}
+
+#[test]
+fn it_points_at_invalid_members() {
+    use jsonapi::errors::{Error, ParseError};
+    use std::str::FromStr;
+
+    let pointer_of = |err: Error| match err {
+        Error::Parse(ParseError::InvalidMember(ref pointer, _)) => pointer.clone(),
+        _ => panic!("unexpected error {}", err),
+    };
+
+    let serialized = r#"{
+        "data": [
+            { "id": "1", "type": "posts" },
+            { "id": "2", "type": "posts", "attributes": [] }
+        ]
+    }"#;
+    assert_eq!(pointer_of(JsonApiDocument::from_str(serialized).unwrap_err()), "/data/1/attributes");
+
+    let serialized = r#"{
+        "data": { "id": "1", "type": "posts", "relationships": {
+            "author": { "data": { "type": "people", "id": 9 } }
+        } }
+    }"#;
+    assert_eq!(
+        pointer_of(JsonApiDocument::from_str(serialized).unwrap_err()),
+        "/data/relationships/author/data/id"
+    );
+
+    let serialized = r#"{ "errors": [{ "status": 404 }] }"#;
+    assert_eq!(pointer_of(JsonApiDocument::from_str(serialized).unwrap_err()), "/errors/0/status");
+
+    let serialized = r#"{ "id": "1", "type": "posts", "links": { "self": "/posts/1" }, "meta": 1 }"#;
+    assert_eq!(pointer_of(Resource::from_str(serialized).unwrap_err()), "/meta");
+
+    let err = Resource::from_str(r#"{ "id": "1", "type": "posts" } ]"#).unwrap_err();
+    assert_eq!(pointer_of(err), "");
+}
+
+#[test]
+fn it_converts_invalid_members_into_errors() {
+    use std::str::FromStr;
+
+    let err = JsonApiDocument::from_str(r#"{ "data": { "id": 1, "type": "posts" } }"#).unwrap_err();
+    let error = JsonApiError::from(err);
+    assert_eq!(error.status_code(), Some(400));
+    assert_eq!(error.source.unwrap().pointer, Some("/data/id".into()));
+    assert!(error.detail.unwrap().contains("invalid type: integer `1`, expected a string"));
+}
+
+#[test]
+fn it_round_trips_error_sources() {
+    let errors = vec![
+        serde_json::json!({
+            "status": "422",
+            "source": { "pointer": "/data/attributes/firstName" },
+            "title": "Invalid Attribute",
+            "detail": "First name must contain at least two characters."
+        }),
+        serde_json::json!({
+            "source": { "parameter": "include" },
+            "title": "Invalid Query Parameter",
+            "detail": "The resource does not have an `auther` relationship path."
+        }),
+        serde_json::json!({
+            "status": "400",
+            "source": { "header": "Content-Type" },
+            "detail": "Unsupported media type parameter."
+        }),
+    ];
+    for json in errors {
+        let error: JsonApiError = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&error).unwrap(), json);
+    }
+
+    let s = read_json_file("data/errors.json");
+    let doc: JsonApiDocument = serde_json::from_str(&s).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&s).unwrap();
+    assert_eq!(serde_json::to_value(&doc).unwrap(), json);
+}
+
+#[test]
+fn it_builds_error_sources() {
+    let error = JsonApiError::bad_request().header("Accept");
+    assert_eq!(
+        serde_json::to_value(&error.source).unwrap(),
+        serde_json::json!({ "header": "Accept" })
+    );
+    assert_eq!(serde_json::to_string(&ErrorSource::default()).unwrap(), "{}");
+}
+
+#[test]
+fn it_validates_error_source_pointers() {
+    let document = serde_json::json!({
+        "data": [
+            { "type": "people", "id": "1", "attributes": { "first/name": "J", "~tag": "x" } }
+        ]
+    });
+    let validate = |pointer: &str| {
+        ErrorSource { pointer: Some(pointer.into()), ..Default::default() }.validate_pointer(&document)
+    };
+
+    assert_eq!(validate(""), Ok(()));
+    assert_eq!(validate("/data"), Ok(()));
+    assert_eq!(validate("/data/0/attributes/first~1name"), Ok(()));
+    assert_eq!(validate("/data/0/attributes/~0tag"), Ok(()));
+    assert_eq!(validate("/data/1"), Err(PointerValidationError::Unresolved));
+    assert_eq!(validate("/data/00"), Err(PointerValidationError::Unresolved));
+    assert_eq!(validate("/data/-"), Err(PointerValidationError::Unresolved));
+    assert_eq!(validate("/data/0/id/type"), Err(PointerValidationError::Unresolved));
+    assert_eq!(validate("data/0"), Err(PointerValidationError::InvalidSyntax));
+    assert_eq!(validate("/data/0/attributes/~2"), Err(PointerValidationError::InvalidSyntax));
+    assert_eq!(validate("/missing/~"), Err(PointerValidationError::InvalidSyntax));
+
+    assert_eq!(ErrorSource::default().validate_pointer(&document), Ok(()));
+}
+
+#[test]
+fn it_chains_crate_errors() {
+    use jsonapi::errors::{ConversionError, Error};
+    use std::error::Error as StdError;
+    use std::str::FromStr;
+
+    // No message is reported twice along the chain
+    let err: Error = ConversionError::NoData.into();
+    assert_eq!(err.to_string(), "Document had no data");
+    assert!(err.source().is_none());
+
+    let err = JsonApiDocument::from_str(r#"{ "data": { "type": "posts", "id": 1 } }"#).unwrap_err();
+    assert!(err.to_string().starts_with("Invalid document member '/data/id'"));
+    let json_err = err.source().unwrap();
+    assert!(json_err.downcast_ref::<serde_json::Error>().is_some());
+    assert!(json_err.source().is_none());
+
+    let err: Error = ConversionError::ModelSerialization("posts".into(), "key must be a string".into()).into();
+    assert_eq!(err.source().unwrap().to_string(), "key must be a string");
+}
+
+#[test]
+fn it_validates_error_documents() {
+    use std::str::FromStr;
+    let _ = env_logger::try_init();
+
+    let doc = JsonApiDocument::from_str(r#"{ "errors": [{ "status": "404" }, { "status": "422" }] }"#).unwrap();
+    assert_eq!(doc.is_valid(), true);
+
+    let doc = JsonApiDocument::from_str(r#"{ "errors": [] }"#).unwrap();
+    assert_eq!(doc.validate(), Some(vec![DocumentValidationError::MissingErrors]));
+
+    let doc = JsonApiDocument::from_str(r#"{ "errors": [{ "status": "404" }, { "status": "unknown" }, { "status": "302" }] }"#).unwrap();
+    assert_eq!(
+        doc.validate(),
+        Some(vec![
+            DocumentValidationError::InvalidError(1, ErrorValidationError::InvalidStatus),
+            DocumentValidationError::InvalidError(2, ErrorValidationError::NonErrorStatus),
+        ])
+    );
+
+    // The specification allows mixed statuses, responded to with the most general one
+    let doc = JsonApiDocument::from_str(r#"{ "errors": [{ "status": "404" }, { "status": "503" }] }"#).unwrap();
+    assert_eq!(doc.is_valid(), true);
+    match doc {
+        JsonApiDocument::Error(ref doc) => {
+            assert_eq!(doc.status(), Some(500));
+            assert_eq!(doc.validate_statuses(), Some(vec![DocumentValidationError::InconsistentErrorStatuses]));
+        }
+        JsonApiDocument::Data(_) => assert!(false),
+    }
+
+    match JsonApiDocument::from_str(r#"{ "errors": [{ "status": 404 }] }"#) {
+        Err(jsonapi::errors::Error::Parse(jsonapi::errors::ParseError::InvalidMember(ref pointer, _))) => {
+            assert_eq!(pointer, "/errors/0/status")
+        }
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn it_validates_error_objects() {
+    let _ = env_logger::try_init();
+    let with_status = |status: &str| JsonApiError {
+        status: Some(status.into()),
+        ..Default::default()
+    };
+
+    assert_eq!(JsonApiError::default().is_valid(), true);
+    assert_eq!(with_status("400").is_valid(), true);
+    assert_eq!(with_status("599").is_valid(), true);
+    assert_eq!(with_status("200").validate(), Some(vec![ErrorValidationError::NonErrorStatus]));
+    assert_eq!(with_status("600").validate(), Some(vec![ErrorValidationError::InvalidStatus]));
+    assert_eq!(with_status("0404").validate(), Some(vec![ErrorValidationError::InvalidStatus]));
+    assert_eq!(with_status("+404").validate(), Some(vec![ErrorValidationError::InvalidStatus]));
+
+    let error = JsonApiError::from(DocumentValidationError::InvalidError(2, ErrorValidationError::InvalidStatus));
+    assert_eq!(error.source.unwrap().pointer, Some("/errors/2/status".into()));
+}
+
+#[test]
+fn it_parses_documents_strictly() {
+    use jsonapi::errors::{Error, ParseError};
+    use std::str::FromStr;
+    let _ = env_logger::try_init();
+
+    let strict_error = |res: Result<JsonApiDocument, Error>| match res {
+        Err(Error::Parse(ParseError::Strict(err))) => err,
+        _ => panic!("expected a strict document error"),
+    };
+
+    let serialized = r#"{ "data": { "id": "1", "type": "posts" }, "errors": [{ "status": "500" }] }"#;
+    match JsonApiDocument::from_str(serialized).unwrap() {
+        JsonApiDocument::Error(_) => (),
+        JsonApiDocument::Data(_) => assert!(false),
+    }
+    assert_eq!(
+        strict_error(StrictParser::new().parse(serialized)),
+        StrictDocumentError::DataAndErrors
+    );
+
+    let serialized = r#"{ "meta": {}, "version:id": "42", "@context": "https://example.com" }"#;
+    assert_eq!(
+        strict_error(StrictParser::new().parse(serialized)),
+        StrictDocumentError::UnknownMember("version:id".into())
+    );
+    let doc: JsonApiDocument = StrictParser::new().extension("version").parse(serialized).unwrap();
+    assert_eq!(doc.is_valid(), true);
+
+    let serialized = r#"{ "data": null, "sideloaded": [] }"#;
+    let err = StrictParser::new().parse::<Meta>(serialized).unwrap_err();
+    assert_eq!(err.to_string(), "Invalid document: Unknown top-level member 'sideloaded'");
+    let error = JsonApiError::from(err);
+    assert_eq!(error.code, Some("unknown_member".into()));
+    assert_eq!(error.source.unwrap().pointer, Some("/sideloaded".into()));
+
+    let doc: JsonApiDocument = StrictParser::new()
+        .parse(r#"{ "errors": [{ "status": "404" }], "links": {}, "jsonapi": { "version": "1.1" } }"#)
+        .unwrap();
+    assert_eq!(doc.is_valid(), true);
+
+    let value = serde_json::json!({ "data": [], "included": [] });
+    assert_eq!(StrictParser::new().check(&value), Ok(()));
+    assert!(StrictParser::new().from_value::<Meta>(serde_json::json!([])).is_err());
+}
+
+#[test]
+fn it_diffs_and_patches_relationships() {
+    let _ = env_logger::try_init();
+    let resource = |relationships: serde_json::Value| -> Resource {
+        serde_json::from_value(serde_json::json!({
+            "type": "posts",
+            "id": "1",
+            "attributes": {},
+            "relationships": relationships
+        }))
+        .unwrap()
+    };
+    let person = |id: &str| serde_json::json!({ "type": "people", "id": id });
+    let tag = |id: &str| serde_json::json!({ "type": "tags", "id": id });
+
+    let mut before = resource(serde_json::json!({
+        "author": { "data": person("9") },
+        "editor": { "data": person("3") },
+        "tags": { "data": [tag("1"), tag("2"), tag("3")] }
+    }));
+    let after = resource(serde_json::json!({
+        "author": { "data": person("10") },
+        "reviewer": { "data": null },
+        "tags": { "data": [tag("2"), tag("4")] }
+    }));
+
+    let patchset = before.diff(after.clone()).unwrap();
+    let summary: Vec<_> = patchset
+        .patches
+        .iter()
+        .map(|patch| (patch.operation, patch.subject.as_str(), patch.path.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (PatchOperation::Replace, "author", ""),
+            (PatchOperation::Remove, "editor", ""),
+            (PatchOperation::Add, "reviewer", ""),
+            (PatchOperation::Remove, "tags", "/2"),
+            (PatchOperation::Remove, "tags", "/0"),
+            (PatchOperation::Add, "tags", "/-"),
+        ]
+    );
+    assert!(patchset.patches.iter().all(|patch| patch.patch_type == PatchType::Relationship));
+    assert_eq!(patchset.patches[0].previous, person("9"));
+    assert_eq!(patchset.patches[0].next, person("10"));
+    assert_eq!(patchset.patches[3].previous, tag("3"));
+    assert_eq!(patchset.patches[5].next, tag("4"));
+
+    assert_eq!(before.patch(patchset).unwrap(), after);
+}
+
+#[test]
+fn it_rejects_relationship_patches_that_do_not_apply() {
+    let _ = env_logger::try_init();
+    let mut resource: Resource = serde_json::from_value(serde_json::json!({
+        "type": "posts",
+        "id": "1",
+        "relationships": { "author": { "data": { "type": "people", "id": "9" } } }
+    }))
+    .unwrap();
+    let patch = |subject: &str| Patch {
+        patch_type: PatchType::Relationship,
+        operation: PatchOperation::Add,
+        subject: subject.into(),
+        path: "/-".into(),
+        previous: serde_json::Value::Null,
+        next: serde_json::json!({ "type": "people", "id": "10" }),
+    };
+
+    let mut patchset = PatchSet::new_for(&resource);
+    patchset.push(patch("author"));
+    match resource.patch(patchset) {
+        Err(jsonapi::errors::Error::Patch(DiffPatchError::IncorrectPropertyValue(ref subject))) => {
+            assert_eq!(subject, "author/-")
+        }
+        _ => assert!(false),
+    }
+
+    let mut patchset = PatchSet::new_for(&resource);
+    patchset.push(patch("editors"));
+    match resource.patch(patchset) {
+        Err(jsonapi::errors::Error::Patch(DiffPatchError::NonExistentProperty(ref subject))) => {
+            assert_eq!(subject, "editors/-")
+        }
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn it_diffs_resources_with_different_attributes() {
+    let _ = env_logger::try_init();
+    let post = |attributes: serde_json::Value| -> Resource {
+        serde_json::from_value(serde_json::json!({ "type": "posts", "id": "1", "attributes": attributes })).unwrap()
+    };
+
+    let mut before = post(serde_json::json!({
+        "title": "Rails is Omakase",
+        "likes": 250,
+        "address": { "city": "Utrecht", "zip/code": "3500" },
+        "tags": ["rails", "news", "old"]
+    }));
+    let after = post(serde_json::json!({
+        "title": "Rails is Omakase",
+        "published": true,
+        "address": { "city": "Amsterdam", "country": "NL" },
+        "tags": ["rails", "updates"]
+    }));
+
+    let patchset = before.diff(after.clone()).unwrap();
+    let summary: Vec<_> = patchset
+        .patches
+        .iter()
+        .map(|patch| (patch.operation, patch.subject.as_str(), patch.path.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (PatchOperation::Replace, "address", "/city"),
+            (PatchOperation::Add, "address", "/country"),
+            (PatchOperation::Remove, "address", "/zip~1code"),
+            (PatchOperation::Remove, "likes", ""),
+            (PatchOperation::Add, "published", ""),
+            (PatchOperation::Replace, "tags", "/1"),
+            (PatchOperation::Remove, "tags", "/2"),
+        ]
+    );
+    assert_eq!(patchset.patches[0].previous, serde_json::json!("Utrecht"));
+    assert_eq!(patchset.patches[0].next, serde_json::json!("Amsterdam"));
+    assert_eq!(patchset.patches[3].previous, serde_json::json!(250));
+
+    assert_eq!(before.patch(patchset).unwrap(), after);
+    assert_eq!(after.diff(after.clone()).unwrap().patches.len(), 0);
+}
+
+#[test]
+fn it_rejects_attribute_patches_that_do_not_apply() {
+    let _ = env_logger::try_init();
+    let mut resource: Resource = serde_json::from_value(serde_json::json!({
+        "type": "posts",
+        "id": "1",
+        "attributes": { "title": "Rails is Omakase", "tags": ["rails"] }
+    }))
+    .unwrap();
+    let mut patch_with = |operation, subject: &str, path: &str| {
+        let mut patchset = PatchSet::new_for(&resource);
+        patchset.push(Patch {
+            patch_type: PatchType::Attribute,
+            operation,
+            subject: subject.into(),
+            path: path.into(),
+            previous: serde_json::Value::Null,
+            next: serde_json::json!("news"),
+        });
+        match resource.patch(patchset) {
+            Err(jsonapi::errors::Error::Patch(err)) => Err(err),
+            Err(err) => panic!("unexpected error {}", err),
+            Ok(resource) => Ok(resource),
+        }
+    };
+
+    assert!(patch_with(PatchOperation::Add, "tags", "/-").is_ok());
+    assert!(patch_with(PatchOperation::Add, "tags", "/1").is_ok());
+    assert_eq!(
+        patch_with(PatchOperation::Remove, "likes", "").unwrap_err(),
+        DiffPatchError::NonExistentProperty("likes".into())
+    );
+    assert_eq!(
+        patch_with(PatchOperation::Replace, "tags", "/1").unwrap_err(),
+        DiffPatchError::NonExistentProperty("tags/1".into())
+    );
+    assert_eq!(
+        patch_with(PatchOperation::Add, "tags", "/2").unwrap_err(),
+        DiffPatchError::NonExistentProperty("tags/2".into())
+    );
+    assert_eq!(
+        patch_with(PatchOperation::Replace, "title", "/length").unwrap_err(),
+        DiffPatchError::IncorrectPropertyValue("title/length".into())
+    );
+    assert_eq!(
+        patch_with(PatchOperation::Replace, "tags", "/first").unwrap_err(),
+        DiffPatchError::IncorrectPropertyValue("tags/first".into())
+    );
+}
+
+#[test]
+fn it_checks_previous_values_when_patching() {
+    let _ = env_logger::try_init();
+    let post = |title: &str, tags: serde_json::Value, author: &str| -> Resource {
+        serde_json::from_value(serde_json::json!({
+            "type": "posts",
+            "id": "1",
+            "attributes": { "title": title, "tags": tags },
+            "relationships": {
+                "author": { "data": { "type": "people", "id": author } }
+            }
+        }))
+        .unwrap()
+    };
+
+    let base = post("Rails", serde_json::json!(["rails", "news"]), "9");
+    let patchset = base
+        .diff(post("Rails is Omakase", serde_json::json!(["rails", "updates"]), "10"))
+        .unwrap();
+
+    let mut current = post("Rails on Rust", serde_json::json!(["ruby", "news"]), "9");
+    assert_eq!(current.conflicts(&patchset), vec![DiffPatchError::IncorrectPropertyValue("title".into())]);
+    match current.patch(patchset.clone()) {
+        Err(jsonapi::errors::Error::Patch(DiffPatchError::IncorrectPropertyValue(ref property))) => {
+            assert_eq!(property, "title")
+        }
+        _ => assert!(false),
+    }
+
+    let mut current = post("Rails", serde_json::json!(["rails", "old"]), "3");
+    assert_eq!(
+        current.conflicts(&patchset),
+        vec![
+            DiffPatchError::IncorrectPropertyValue("author".into()),
+            DiffPatchError::IncorrectPropertyValue("tags/1".into()),
+        ]
+    );
+    assert!(current.patch(patchset.clone()).is_err());
+
+    let mut current = base.clone();
+    assert_eq!(current.conflicts(&patchset), vec![]);
+    assert!(current.patch(patchset).is_ok());
+}
+
+#[test]
+fn it_merges_patch_sets() {
+    let _ = env_logger::try_init();
+    let post = |attributes: serde_json::Value, comments: &[&str]| -> Resource {
+        let comments: Vec<_> = comments
+            .iter()
+            .map(|id| serde_json::json!({ "type": "comments", "id": id }))
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "type": "posts",
+            "id": "1",
+            "attributes": attributes,
+            "relationships": { "comments": { "data": comments } }
+        }))
+        .unwrap()
+    };
+
+    let base = post(serde_json::json!({ "title": "Rails", "likes": 1, "tags": ["rails"] }), &["1", "2"]);
+    let ours = base
+        .diff(post(serde_json::json!({ "title": "Rails is Omakase", "likes": 1, "tags": ["rails"] }), &["2", "3"]))
+        .unwrap();
+    let theirs = base
+        .diff(post(serde_json::json!({ "title": "Rails", "likes": 2, "tags": ["rails", "news"] }), &["1", "2", "3", "4"]))
+        .unwrap();
+
+    let merged = base.merge(&ours, &theirs).unwrap();
+    assert_eq!(
+        merged,
+        post(serde_json::json!({ "title": "Rails is Omakase", "likes": 2, "tags": ["rails", "news"] }), &["2", "3", "4"])
+    );
+
+    // Both change the title
+    let theirs = base
+        .diff(post(serde_json::json!({ "title": "Rails on Rust", "likes": 1, "tags": ["rails"] }), &["1", "2"]))
+        .unwrap();
+    match ours.merge(&theirs, &base) {
+        Err(DiffPatchError::MergeConflicts(conflicts)) => {
+            assert_eq!(conflicts.len(), 1);
+            assert_eq!(conflicts[0].ours.subject, "title");
+            assert_eq!(conflicts[0].theirs.next, "Rails on Rust");
+        }
+        _ => assert!(false),
+    }
+
+    // One removes an element of an array the other changes a later element of
+    let base = post(serde_json::json!({ "tags": ["rails", "news", "old"] }), &[]);
+    let ours = base.diff(post(serde_json::json!({ "tags": ["news", "old"] }), &[])).unwrap();
+    let theirs = base.diff(post(serde_json::json!({ "tags": ["rails", "news", "new"] }), &[])).unwrap();
+    match base.merge(&ours, &theirs) {
+        Err(jsonapi::errors::Error::Patch(DiffPatchError::MergeConflicts(conflicts))) => assert!(!conflicts.is_empty()),
+        _ => assert!(false),
+    }
+
+    let other: Resource = serde_json::from_value(serde_json::json!({ "type": "people", "id": "1" })).unwrap();
+    assert_eq!(
+        ours.merge(&theirs, &other).unwrap_err(),
+        DiffPatchError::IncompatibleTypes("people".into(), "posts".into())
+    );
+
+    let other: Resource = serde_json::from_value(serde_json::json!({ "type": "posts", "id": "2" })).unwrap();
+    assert_eq!(
+        ours.merge(&theirs, &other).unwrap_err(),
+        DiffPatchError::IncompatibleIds("2".into(), "1".into())
+    );
+}
+
+#[test]
+fn it_exports_patch_sets_as_json_patch() {
+    let _ = env_logger::try_init();
+    let post = |title: &str, author: &str, comments: &[&str]| -> Resource {
+        let comments: Vec<_> = comments
+            .iter()
+            .map(|id| serde_json::json!({ "type": "comments", "id": id }))
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "type": "posts",
+            "id": "1",
+            "attributes": { "title": title, "tags": ["rails"] },
+            "relationships": {
+                "author": { "data": { "type": "people", "id": author } },
+                "comments": { "data": comments }
+            }
+        }))
+        .unwrap()
+    };
+
+    let base = post("Rails", "9", &["1", "2"]);
+    let next = post("Rails is Omakase", "10", &["2", "3"]);
+    let patchset = base.diff(next.clone()).unwrap();
+
+    let operations = patchset.to_json_patch(&base);
+    assert_eq!(
+        serde_json::to_value(&operations).unwrap(),
+        serde_json::json!([
+            { "op": "test", "path": "/relationships/author/data", "value": { "type": "people", "id": "9" } },
+            { "op": "replace", "path": "/relationships/author/data", "value": { "type": "people", "id": "10" } },
+            { "op": "test", "path": "/relationships/comments/data/0", "value": { "type": "comments", "id": "1" } },
+            { "op": "remove", "path": "/relationships/comments/data/0" },
+            { "op": "add", "path": "/relationships/comments/data/-", "value": { "type": "comments", "id": "3" } },
+            { "op": "test", "path": "/attributes/title", "value": "Rails" },
+            { "op": "replace", "path": "/attributes/title", "value": "Rails is Omakase" }
+        ])
+    );
+
+    let imported = PatchSet::from_json_patch(&base, &operations).unwrap();
+    assert_eq!(imported, patchset);
+    assert_eq!(base.clone().patch(imported).unwrap(), next);
+
+    // The operations apply to the resource object as any JSON Patch would
+    let mut document = serde_json::to_value(&base).unwrap();
+    for operation in &operations {
+        if let JsonPatchOperation::Replace { ref path, ref value } = *operation {
+            *document.pointer_mut(path).unwrap() = value.clone();
+        }
+    }
+    assert_eq!(document["attributes"]["title"], "Rails is Omakase");
+}
+
+#[test]
+fn it_exports_relationships_added_to_resources_without_any() {
+    let _ = env_logger::try_init();
+    let base: Resource = serde_json::from_value(serde_json::json!({ "type": "posts", "id": "1" })).unwrap();
+    let next: Resource = serde_json::from_value(serde_json::json!({
+        "type": "posts",
+        "id": "1",
+        "relationships": {
+            "author": {
+                "data": { "type": "people", "id": "9" },
+                "links": { "related": "/posts/1/author" }
+            },
+            "comments": { "data": [] }
+        }
+    }))
+    .unwrap();
+    let patchset = base.diff(next).unwrap();
+
+    // The resource object has no `relationships` member to add them to
+    let operations = patchset.to_json_patch(&base);
+    assert_eq!(
+        serde_json::to_value(&operations).unwrap(),
+        serde_json::json!([
+            {
+                "op": "add",
+                "path": "/relationships",
+                "value": { "author": { "data": { "type": "people", "id": "9" } } }
+            },
+            { "op": "add", "path": "/relationships/comments", "value": { "data": [] } }
+        ])
+    );
+    let imported = PatchSet::from_json_patch(&base, &operations).unwrap();
+    assert_eq!(imported, patchset);
+
+    // Relationship links are not part of patches, so they are lost both ways
+    let operations: Vec<JsonPatchOperation> = serde_json::from_value(serde_json::json!([{
+        "op": "add",
+        "path": "/relationships",
+        "value": {
+            "author": { "data": { "type": "people", "id": "9" }, "links": { "related": "/posts/1/author" } }
+        }
+    }]))
+    .unwrap();
+    let patched = base.clone().patch(PatchSet::from_json_patch(&base, &operations).unwrap()).unwrap();
+    let author = &patched.relationships.unwrap()["author"];
+    assert_eq!(author.data, Some(IdentifierData::Single(ResourceIdentifier { _type: "people".into(), id: "9".into() })));
+    assert!(author.links.is_none());
+}
+
+#[test]
+fn it_imports_json_patch() {
+    let _ = env_logger::try_init();
+    let base: Resource = serde_json::from_value(serde_json::json!({
+        "type": "posts",
+        "id": "1",
+        "attributes": { "title": "Rails", "subtitle": "Omakase", "tags": ["rails"] },
+        "relationships": { "author": { "data": { "type": "people", "id": "9" } } }
+    }))
+    .unwrap();
+    let import = |operations: serde_json::Value| {
+        let operations: Vec<JsonPatchOperation> = serde_json::from_value(operations).unwrap();
+        PatchSet::from_json_patch(&base, &operations)
+    };
+
+    let patchset = import(serde_json::json!([
+        { "op": "add", "path": "/attributes/tags/0", "value": "news" },
+        { "op": "add", "path": "/attributes/title", "value": "Rails on Rust" },
+        { "op": "move", "from": "/attributes/subtitle", "path": "/attributes/summary" },
+        { "op": "copy", "from": "/relationships/author/data", "path": "/relationships/editor/data" },
+        { "op": "remove", "path": "/relationships/author" }
+    ]))
+    .unwrap();
+    let patched = base.clone().patch(patchset).unwrap();
+    assert_eq!(
+        serde_json::to_value(&patched).unwrap(),
+        serde_json::json!({
+            "type": "posts",
+            "id": "1",
+            "attributes": { "title": "Rails on Rust", "summary": "Omakase", "tags": ["news", "rails"] },
+            "relationships": { "editor": { "data": { "type": "people", "id": "9" } } }
+        })
+    );
+
+    assert_eq!(
+        import(serde_json::json!([{ "op": "test", "path": "/attributes/title", "value": "Django" }])).unwrap_err(),
+        DiffPatchError::IncorrectPropertyValue("/attributes/title".into())
+    );
+    assert_eq!(
+        import(serde_json::json!([{ "op": "replace", "path": "/type", "value": "people" }])).unwrap_err(),
+        DiffPatchError::ImmutableProperty("/type".into())
+    );
+    assert_eq!(
+        import(serde_json::json!([{ "op": "move", "from": "/id", "path": "/attributes/id" }])).unwrap_err(),
+        DiffPatchError::ImmutableProperty("/id".into())
+    );
+    assert_eq!(
+        import(serde_json::json!([{ "op": "add", "path": "/links/self", "value": "/posts/1" }])).unwrap_err(),
+        DiffPatchError::UnsupportedPath("/links/self".into())
+    );
+    assert_eq!(
+        import(serde_json::json!([{ "op": "remove", "path": "/relationships" }])).unwrap_err(),
+        DiffPatchError::UnsupportedPath("/relationships".into())
+    );
+    assert_eq!(
+        import(serde_json::json!([{ "op": "add", "path": "/relationships/author/links", "value": {} }])).unwrap_err(),
+        DiffPatchError::UnsupportedPath("/relationships/author/links".into())
+    );
+    assert_eq!(
+        import(serde_json::json!([{ "op": "remove", "path": "/attributes/likes" }])).unwrap_err(),
+        DiffPatchError::NonExistentProperty("/attributes/likes".into())
+    );
+}
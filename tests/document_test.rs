@@ -0,0 +1,200 @@
+#![allow(clippy::assertions_on_constants)]
+#[macro_use]
+extern crate jsonapi;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+use jsonapi::api::*;
+use jsonapi::document::*;
+use jsonapi::model::*;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Author {
+    id: String,
+    name: String,
+    books: Vec<Book>,
+}
+jsonapi_model!(Author; "authors"; has many books);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Book {
+    id: String,
+    title: String,
+}
+jsonapi_model!(Book; "books");
+
+fn tolkien() -> Author {
+    Author {
+        id: "1".into(),
+        name: "J. R. R. Tolkien".into(),
+        books: vec![
+            Book { id: "1".into(), title: "The Fellowship of the Ring".into() },
+            Book { id: "2".into(), title: "The Two Towers".into() },
+        ],
+    }
+}
+
+#[test]
+fn document_to_json_and_back() {
+    let mut meta = Meta::new();
+    meta.insert("copyright".into(), json!("Tolkien Estate"));
+    let mut links = Links::new();
+    links.insert("self".into(), json!("/authors/1"));
+
+    let doc = Document {
+        data: tolkien(),
+        links: Some(links),
+        meta: Some(meta),
+        jsonapi: Some(JsonApiInfo { version: Some("1.0".into()), meta: None }),
+    };
+
+    let json = serde_json::to_value(&doc).unwrap();
+    assert_eq!(json["data"]["type"], "authors");
+    assert_eq!(json["data"]["attributes"]["name"], "J. R. R. Tolkien");
+    assert_eq!(json["included"].as_array().unwrap().len(), 2);
+    assert_eq!(json["links"]["self"], "/authors/1");
+    assert_eq!(json["meta"]["copyright"], "Tolkien Estate");
+    assert_eq!(json["jsonapi"]["version"], "1.0");
+
+    let doc_again: Document<Author> = serde_json::from_value(json).unwrap();
+    assert_eq!(doc_again, doc);
+}
+
+#[test]
+fn document_from_model() {
+    let doc = Document::from(tolkien());
+    assert_eq!(doc.links, None);
+    assert_eq!(doc.meta, None);
+    assert_eq!(doc.jsonapi, None);
+
+    let json = serde_json::to_value(&doc).unwrap();
+    assert!(json.get("links").is_none());
+    assert!(json.get("meta").is_none());
+    assert!(json.get("jsonapi").is_none());
+}
+
+#[test]
+fn collection_document_to_json_and_back() {
+    let books = tolkien().books;
+    let mut doc = CollectionDocument::from(books.clone());
+    let mut meta = Meta::new();
+    meta.insert("total".into(), json!(2));
+    doc.meta = Some(meta);
+
+    let json = serde_json::to_value(&doc).unwrap();
+    assert_eq!(json["data"].as_array().unwrap().len(), 2);
+    assert_eq!(json["data"][1]["attributes"]["title"], "The Two Towers");
+    assert_eq!(json["meta"]["total"], 2);
+    assert!(json.get("included").is_none());
+
+    let doc_again: CollectionDocument<Book> = serde_json::from_value(json).unwrap();
+    assert_eq!(doc_again.data, books);
+    assert_eq!(doc_again, doc);
+}
+
+#[test]
+fn collection_document_includes_related_resources() {
+    let doc = CollectionDocument::new(vec![tolkien()]);
+    let data = doc.to_document_data_with(&ConversionContext::default()).unwrap();
+    assert_eq!(data.included.unwrap().len(), 2);
+}
+
+#[test]
+fn document_with_invalid_data() {
+    let json = r#"{"data": {"type": "authors", "id": "1", "attributes": {}}}"#;
+    let res: Result<Document<Author>, _> = serde_json::from_str(json);
+    assert!(res.is_err());
+
+    let json = r#"{"errors": [{"status": "404"}]}"#;
+    let res: Result<CollectionDocument<Book>, _> = serde_json::from_str(json);
+    assert!(res.unwrap_err().to_string().contains("found an error document"));
+    let res: Result<Document<Author>, _> = serde_json::from_str(json);
+    assert!(res.unwrap_err().to_string().contains("found an error document"));
+}
+
+#[test]
+fn document_with_context() {
+    let json = r#"{"data": {"type": "writers", "id": "1", "attributes": {"name": "Tolkien"}}}"#;
+    let data: DocumentData = serde_json::from_str(json).unwrap();
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Writer {
+        id: String,
+        name: String,
+    }
+    jsonapi_model!(Writer; "authors");
+
+    assert!(Document::<Writer>::from_document_data_with(data.clone(), &ConversionContext::default()).is_err());
+
+    let ctx = ConversionContext {
+        check_type: false,
+        ..Default::default()
+    };
+    let doc = Document::<Writer>::from_document_data_with(data, &ctx).unwrap();
+    assert_eq!(doc.data.name, "Tolkien");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PageMeta {
+    total: u64,
+}
+
+#[test]
+fn collection_document_with_typed_meta() {
+    let json = r#"{
+        "data": [{"type": "books", "id": "1", "attributes": {"title": "The Hobbit"}}],
+        "meta": {"total": 12}
+    }"#;
+    let doc: GenericCollectionDocument<Book, PageMeta> = serde_json::from_str(json).unwrap();
+    assert_eq!(doc.meta, Some(PageMeta { total: 12 }));
+    assert_eq!(doc.data[0].title, "The Hobbit");
+
+    let json = serde_json::to_value(&doc).unwrap();
+    assert_eq!(json["meta"], json!({"total": 12}));
+
+    let data = doc.to_document_data_with(&ConversionContext::default()).unwrap();
+    assert_eq!(data.meta.unwrap().total, 12);
+}
+
+#[test]
+fn typed_meta_is_type_checked() {
+    let json = r#"{"data": [], "meta": {"total": "many"}}"#;
+    let res: Result<GenericCollectionDocument<Book, PageMeta>, _> = serde_json::from_str(json);
+    assert!(res.is_err());
+
+    let res: Result<GenericDocumentData<PageMeta>, _> = serde_json::from_str(json);
+    assert!(res.is_err());
+}
+
+#[test]
+fn resource_with_typed_meta() {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Revision {
+        revision: u32,
+    }
+
+    let json = r#"{"type": "books", "id": "1", "attributes": {}, "meta": {"revision": 3}}"#;
+    let resource: GenericResource<Revision> = serde_json::from_str(json).unwrap();
+    assert_eq!(resource.meta, Some(Revision { revision: 3 }));
+
+    let doc: GenericDocumentData<PageMeta> = GenericDocumentData {
+        meta: Some(PageMeta { total: 0 }),
+        ..Default::default()
+    };
+    assert_eq!(serde_json::to_string(&doc).unwrap(), r#"{"meta":{"total":0}}"#);
+
+    // The resources of a document have their own meta type
+    let json = r#"{
+        "data": {"type": "books", "id": "1", "meta": {"revision": 3}},
+        "included": [{"type": "authors", "id": "1", "meta": {"revision": 1}}],
+        "meta": {"total": 1}
+    }"#;
+    let doc: GenericDocumentData<PageMeta, Revision> = serde_json::from_str(json).unwrap();
+    match doc.data {
+        Some(GenericPrimaryData::Single(ref resource)) => assert_eq!(resource.meta, Some(Revision { revision: 3 })),
+        _ => assert!(false),
+    }
+    assert_eq!(doc.included.unwrap()[0].meta, Some(Revision { revision: 1 }));
+}
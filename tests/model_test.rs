@@ -1,9 +1,12 @@
+#![allow(clippy::assertions_on_constants)]
 #[macro_use]
 extern crate jsonapi;
 #[macro_use]
 extern crate serde_derive;
+extern crate serde;
 extern crate serde_json;
-use jsonapi::array::JsonApiArray;
+use jsonapi::document::CollectionDocument;
+use jsonapi::errors::{ConversionError, Error, ParseError};
 use jsonapi::model::*;
 
 mod helper;
@@ -130,3 +133,1178 @@ fn from_jsonapi_document() {
         }
     }
 }
+
+#[test]
+fn included_index_resolves_identifiers() {
+    let included = vec![
+        Resource { _type: "books".into(), id: "1".into(), ..Default::default() },
+        Resource { _type: "chapters".into(), id: "1".into(), ..Default::default() },
+        Resource { _type: "chapters".into(), id: "2".into(), ..Default::default() },
+    ];
+    let index = IncludedIndex::new(&included);
+    assert_eq!(index.len(), 3);
+
+    let chapter = ResourceIdentifier { _type: "chapters".into(), id: "1".into() };
+    assert_eq!(index.get(&chapter), Some(&included[1]));
+    assert_eq!(index.get(&chapter), included.iter().find(|r| r._type == chapter._type && r.id == chapter.id));
+
+    let missing = ResourceIdentifier { _type: "books".into(), id: "2".into() };
+    assert_eq!(index.get(&missing), None);
+}
+
+#[test]
+fn from_jsonapi_document_with_many_included() {
+    let books: Vec<Book> = (0..200)
+        .map(|i| Book {
+            id: i.to_string(),
+            title: format!("Book {}", i),
+            first_chapter: Chapter { id: format!("{}-1", i), title: "Chapter 1".into(), ordering: 1 },
+            chapters: (0..5)
+                .map(|c| Chapter { id: format!("{}-{}", i, c), title: format!("Chapter {}", c), ordering: c })
+                .collect(),
+        })
+        .collect();
+    let author = Author { id: "1".into(), name: "J. R. R. Tolkien".into(), books };
+
+    let doc = author.to_jsonapi_document();
+    let json = serde_json::to_string(&doc).unwrap();
+    let author_doc: DocumentData = serde_json::from_str(&json)
+        .expect("Author DocumentData should be created from the author json");
+    let author_again = Author::from_jsonapi_document(&author_doc)
+        .expect("Author should be generated from the author_doc");
+
+    assert_eq!(author, author_again);
+}
+
+#[test]
+fn test_collection_from_jsonapi_document() {
+    let json = ::read_json_file("data/author_tolkien.json");
+    let author_doc: DocumentData = serde_json::from_str(&json)
+        .expect("Author DocumentData should be created from the author json");
+    let authors = CollectionDocument::<Author>::from_document_data_with(author_doc, &ConversionContext::default())
+        .expect("Authors should be generated from the author_doc")
+        .data;
+    assert_eq!(authors.len(), 1);
+    assert_eq!(authors[0].books.len(), 3);
+
+    let chapters = vec![
+        Chapter { id: "45".into(), title: "The Passing of the Grey Company".into(), ordering: 2 },
+        Chapter { id: "46".into(), title: "The Muster of Rohan".into(), ordering: 3 },
+    ];
+    match vec_to_jsonapi_document(chapters) {
+        JsonApiDocument::Error(_) => assert!(false),
+        JsonApiDocument::Data(doc) => {
+            let chapters = CollectionDocument::<Chapter>::from_document_data_with(doc, &ConversionContext::default())
+                .expect("Chapters should be generated from the chapters document")
+                .data;
+            assert_eq!(chapters.len(), 2);
+            assert_eq!(chapters[1].title, "The Muster of Rohan");
+        }
+    }
+}
+
+#[test]
+fn to_jsonapi_resource_leaves_out_id_and_relationships() {
+    let book = Book {
+        id: "1".into(),
+        title: "The Two Towers".into(),
+        first_chapter: Chapter { id: "1".into(), title: "The Departure of Boromir".into(), ordering: 1 },
+        chapters: vec![],
+    };
+
+    let (resource, included) = book.to_jsonapi_resource();
+    assert_eq!(resource.attributes.len(), 1);
+    assert_eq!(resource.attributes["title"], "The Two Towers");
+
+    let relationships = resource.relationships.expect("Book should have relationships");
+    assert!(relationships.contains_key("first_chapter"));
+    assert!(relationships.contains_key("chapters"));
+    assert_eq!(included.map(|i| i.len()), Some(1));
+}
+
+#[test]
+fn from_jsonapi_resource_with_cyclic_included() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Editor {
+        id: String,
+        name: String,
+        #[serde(default)]
+        editions: Vec<Edition>,
+    }
+    jsonapi_model!(Editor; "editors"; has many editions);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Edition {
+        id: String,
+        year: u16,
+        #[serde(default)]
+        editors: Vec<Editor>,
+    }
+    jsonapi_model!(Edition; "editions"; has many editors);
+
+    let doc: DocumentData = serde_json::from_str(r#"{
+        "data": {
+            "type": "editors",
+            "id": "1",
+            "attributes": { "name": "Christopher Tolkien" },
+            "relationships": {
+                "editions": { "data": [{ "type": "editions", "id": "1" }] }
+            }
+        },
+        "included": [{
+            "type": "editions",
+            "id": "1",
+            "attributes": { "year": 1977 },
+            "relationships": {
+                "editors": { "data": [{ "type": "editors", "id": "1" }] }
+            }
+        }, {
+            "type": "editors",
+            "id": "1",
+            "attributes": { "name": "Christopher Tolkien" },
+            "relationships": {
+                "editions": { "data": [{ "type": "editions", "id": "1" }] }
+            }
+        }]
+    }"#).expect("Editor DocumentData should be created from the editor json");
+
+    let editor = Editor::from_jsonapi_document(&doc)
+        .expect("Editor should be generated from the editor document");
+    assert_eq!(editor.editions.len(), 1);
+    assert_eq!(editor.editions[0].year, 1977);
+    assert_eq!(editor.editions[0].editors.len(), 1);
+    assert_eq!(editor.editions[0].editors[0].name, "Christopher Tolkien");
+    // The relationship back to `editions` has already been followed
+    assert!(editor.editions[0].editors[0].editions.is_empty());
+}
+
+#[test]
+fn try_to_jsonapi_resource_names_the_failing_attribute() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Map {
+        id: String,
+        grid: HashMap<(u8, u8), String>,
+    }
+    jsonapi_model!(Map; "maps");
+
+    let mut grid = HashMap::new();
+    grid.insert((0, 0), "Hobbiton".to_string());
+    let map = Map { id: "1".into(), grid };
+
+    match map.try_to_jsonapi_resource() {
+        Ok(_) => assert!(false),
+        Err(err) => match err {
+            Error::Conversion(ConversionError::AttributeSerialization(ref model, ref field, ref err)) => {
+                assert_eq!(model, "maps");
+                assert_eq!(field, "grid");
+                assert_eq!(err.to_string(), "key must be a string");
+            }
+            _ => assert!(false),
+        },
+    }
+    assert!(map.try_to_jsonapi_document().is_err());
+}
+
+#[test]
+fn try_to_jsonapi_resource_rejects_models_that_are_not_objects() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Opaque {
+        id: String,
+    }
+    impl serde::Serialize for Opaque {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&self.id)
+        }
+    }
+    jsonapi_model!(Opaque; "opaque");
+
+    let opaque = Opaque { id: "1".into() };
+    match opaque.try_to_jsonapi_resource() {
+        Ok(_) => assert!(false),
+        Err(err) => match err {
+            Error::Conversion(ConversionError::ModelSerialization(ref model, _)) => assert_eq!(model, "opaque"),
+            _ => assert!(false),
+        },
+    }
+}
+
+#[test]
+fn try_to_jsonapi_resource_fails_on_related_models() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Region {
+        id: String,
+        name: String,
+        places: HashMap<(u8, u8), String>,
+    }
+    jsonapi_model!(Region; "regions");
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct World {
+        id: String,
+        regions: Vec<Region>,
+    }
+    jsonapi_model!(World; "worlds"; has many regions);
+
+    let mut places = HashMap::new();
+    places.insert((1, 2), "Bree".to_string());
+    let world = World {
+        id: "1".into(),
+        regions: vec![Region { id: "1".into(), name: "Eriador".into(), places }],
+    };
+
+    match world.try_to_jsonapi_resource() {
+        Ok(_) => assert!(false),
+        Err(err) => match err {
+            Error::Conversion(ConversionError::AttributeSerialization(ref model, ref field, _)) => {
+                assert_eq!(model, "regions");
+                assert_eq!(field, "places");
+            }
+            _ => assert!(false),
+        },
+    }
+}
+
+#[test]
+#[should_panic(expected = "attribute 'grid' of Model 'maps'")]
+fn to_jsonapi_resource_panics_when_conversion_fails() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Map {
+        id: String,
+        grid: HashMap<(u8, u8), String>,
+    }
+    jsonapi_model!(Map; "maps");
+
+    let mut grid = HashMap::new();
+    grid.insert((0, 0), "Hobbiton".to_string());
+    let _ = Map { id: "1".into(), grid }.to_jsonapi_resource();
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Shelf {
+    id: u32,
+    label: String,
+    volumes: Vec<Volume>,
+}
+jsonapi_model!(Shelf; "shelves"; has many volumes);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Volume {
+    id: i64,
+    title: String,
+}
+jsonapi_model!(Volume; "volumes");
+
+#[test]
+fn numeric_ids_round_trip() {
+    let shelf = Shelf {
+        id: 7,
+        label: "Middle-earth".into(),
+        volumes: vec![
+            Volume { id: -1, title: "The Silmarillion".into() },
+            Volume { id: 42, title: "Unfinished Tales".into() },
+        ],
+    };
+
+    let doc = shelf.to_jsonapi_document();
+    let json = serde_json::to_string(&doc).unwrap();
+    let shelf_doc: DocumentData = serde_json::from_str(&json)
+        .expect("Shelf DocumentData should be created from the shelf json");
+    match shelf_doc.data {
+        Some(PrimaryData::Single(ref resource)) => assert_eq!(resource.id, "7"),
+        _ => assert!(false),
+    }
+
+    let shelf_again = Shelf::from_jsonapi_document(&shelf_doc)
+        .expect("Shelf should be generated from the shelf_doc");
+    assert_eq!(shelf, shelf_again);
+}
+
+#[test]
+fn invalid_numeric_id() {
+    let resource = Resource {
+        _type: "volumes".into(),
+        id: "not-a-number".into(),
+        attributes: vec![("title".to_string(), "The Hobbit".into())].into_iter().collect(),
+        ..Default::default()
+    };
+
+    let err = Volume::from_jsonapi_resource(&resource, &None)
+        .expect_err("A non-numeric id should not convert to an i64");
+    assert!(err.to_string().contains("not-a-number"));
+}
+
+#[test]
+fn string_and_integer_id_types() {
+    use jsonapi::id::JsonApiIdType;
+
+    assert_eq!(42u8.to_jsonapi_id(), "42");
+    assert_eq!(i128::from_jsonapi_id("-170141183460469231731687303715884105728").unwrap(), i128::MIN);
+    assert_eq!(String::from_jsonapi_id("abc").unwrap(), "abc");
+    match u16::from_jsonapi_id("70000") {
+        Err(err) => match err {
+            Error::Conversion(ConversionError::InvalidResourceId(ref id, _)) => assert_eq!(id, "70000"),
+            _ => assert!(false),
+        },
+        Ok(_) => assert!(false),
+    }
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn uuid_ids_round_trip() {
+    extern crate uuid;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Ring {
+        id: uuid::Uuid,
+        name: String,
+    }
+    jsonapi_model!(Ring; "rings");
+
+    let ring = Ring {
+        id: uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap(),
+        name: "The One Ring".into(),
+    };
+
+    let (resource, included) = ring.to_jsonapi_resource();
+    assert_eq!(resource.id, "67e55044-10b1-426f-9247-bb680e5fe0c8");
+    assert_eq!(Ring::from_jsonapi_resource(&resource, &included).unwrap(), ring);
+}
+
+fn fellowship() -> Book {
+    Book {
+        id: "1".into(),
+        title: "The Fellowship of the Ring".into(),
+        first_chapter: Chapter { id: "1".into(), title: "A Long-expected Party".into(), ordering: 1 },
+        chapters: vec![
+            Chapter { id: "1".into(), title: "A Long-expected Party".into(), ordering: 1 },
+            Chapter { id: "2".into(), title: "The Shadow of the Past".into(), ordering: 2 },
+        ],
+    }
+}
+
+#[test]
+fn naming_policy_applies_to_attributes_and_relationships() {
+    let ctx = ConversionContext {
+        naming: NamingPolicy::KebabCase,
+        ..Default::default()
+    };
+    let book = fellowship();
+
+    let doc = book.try_to_jsonapi_document_with(&ctx).unwrap();
+    let json = serde_json::to_value(&doc).unwrap();
+    assert!(json["data"]["relationships"]["first-chapter"].is_object());
+    assert!(json["data"]["relationships"].get("first_chapter").is_none());
+
+    let doc: DocumentData = serde_json::from_value(json).unwrap();
+    assert_eq!(Book::from_jsonapi_document_with(&doc, &ctx).unwrap(), book);
+    // Without the policy the kebab-case relationship is not recognised
+    assert!(Book::from_jsonapi_document(&doc).is_err());
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Reader {
+    id: String,
+    display_name: String,
+    #[serde(rename = "favourite")]
+    favourite_book: Book,
+    #[serde(rename = "borrowedBooks")]
+    borrowed: Vec<Book>,
+}
+jsonapi_model!(Reader; "readers";
+    has one favourite_book as "favourite";
+    has many borrowed as "borrowedBooks";
+    {
+        fn naming_policy() -> Option<NamingPolicy> {
+            Some(NamingPolicy::SnakeCase)
+        }
+    }
+);
+
+#[test]
+fn serde_renamed_relationships() {
+    let reader = Reader {
+        id: "1".into(),
+        display_name: "Frodo".into(),
+        favourite_book: fellowship(),
+        borrowed: vec![fellowship()],
+    };
+
+    // The model's own policy wins over the context, and applies to renamed
+    // fields and relationships alike
+    let ctx = ConversionContext {
+        naming: NamingPolicy::CamelCase,
+        ..Default::default()
+    };
+    let (resource, included) = reader.try_to_jsonapi_resource_with(&ctx).unwrap();
+    let mut attributes: Vec<_> = resource.attributes.keys().cloned().collect();
+    attributes.sort();
+    assert_eq!(attributes, vec!["display_name"]);
+    let mut relationships: Vec<_> = resource.relationships.as_ref().unwrap().keys().cloned().collect();
+    relationships.sort();
+    assert_eq!(relationships, vec!["borrowed_books", "favourite"]);
+
+    // Included books follow the policy of the context
+    let included_book = included.as_ref().unwrap().iter().find(|r| r._type == "books").unwrap();
+    assert!(included_book.relationships.as_ref().unwrap().contains_key("firstChapter"));
+
+    assert_eq!(Reader::from_jsonapi_resource_with(&resource, &included, &ctx).unwrap(), reader);
+}
+
+#[test]
+fn serde_renamed_relationships_need_their_name() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Borrower {
+        id: String,
+        #[serde(rename = "favourite")]
+        favourite_book: Book,
+    }
+    jsonapi_model!(Borrower; "borrowers"; has one favourite_book);
+
+    let borrower = Borrower {
+        id: "1".into(),
+        favourite_book: fellowship(),
+    };
+    match borrower.try_to_jsonapi_resource() {
+        Err(Error::Conversion(ConversionError::ModelSerialization(ref model, ref err))) => {
+            assert_eq!(model, "borrowers");
+            assert!(err.to_string().contains("'favourite_book'"));
+        }
+        _ => assert!(false),
+    }
+
+    // Skipping a relationship field is not renaming it
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Lender {
+        id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        favourite_book: Option<Book>,
+    }
+    jsonapi_model!(Lender; "lenders"; has one favourite_book);
+
+    let lender = Lender {
+        id: "1".into(),
+        favourite_book: None,
+    };
+    let (resource, _) = lender.try_to_jsonapi_resource().unwrap();
+    assert!(resource.attributes.is_empty());
+    assert!(resource.relationships.unwrap().contains_key("favourite_book"));
+}
+
+#[test]
+fn sparse_fieldsets_use_member_names() {
+    let mut fields = HashMap::new();
+    fields.insert("books".to_string(), vec!["firstChapter".to_string()]);
+    fields.insert("chapters".to_string(), vec!["ordering".to_string()]);
+    let ctx = ConversionContext {
+        naming: NamingPolicy::CamelCase,
+        fields: Some(fields),
+        ..Default::default()
+    };
+
+    let (resource, included) = fellowship().try_to_jsonapi_resource_with(&ctx).unwrap();
+    assert!(resource.attributes.is_empty());
+    let relationships = resource.relationships.unwrap();
+    assert_eq!(relationships.keys().collect::<Vec<_>>(), vec!["firstChapter"]);
+    for chapter in included.unwrap() {
+        assert_eq!(chapter.attributes.keys().collect::<Vec<_>>(), vec!["ordering"]);
+    }
+}
+
+#[test]
+fn vec_conversions_with_context() {
+    let ctx = ConversionContext {
+        naming: NamingPolicy::PascalCase,
+        ..Default::default()
+    };
+    let books = vec![fellowship(), fellowship()];
+    match vec_to_jsonapi_document_with(&books, &ctx).unwrap() {
+        JsonApiDocument::Data(doc) => {
+            let again = CollectionDocument::<Book>::from_document_data_with(doc, &ctx).unwrap();
+            assert_eq!(again.data, books);
+        }
+        JsonApiDocument::Error(_) => assert!(false),
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Review {
+    id: String,
+    stars: u8,
+    created_at: String,
+    book: Book,
+}
+jsonapi_model!(Review; "reviews"; has one book; {
+    fn jsonapi_meta(&self) -> Option<Meta> {
+        let mut meta = Meta::new();
+        meta.insert("createdAt".into(), self.created_at.clone().into());
+        Some(meta)
+    }
+});
+
+#[test]
+fn links_from_base_url() {
+    let ctx = ConversionContext {
+        base_url: Some("https://example.com/api/".into()),
+        ..Default::default()
+    };
+    let (resource, included) = fellowship().try_to_jsonapi_resource_with(&ctx).unwrap();
+
+    let links = resource.links.unwrap();
+    assert_eq!(links["self"], "https://example.com/api/books/1");
+    let relationships = resource.relationships.unwrap();
+    let first_chapter = relationships["first_chapter"].links.as_ref().unwrap();
+    assert_eq!(first_chapter["related"], "https://example.com/api/books/1/first_chapter");
+    assert_eq!(first_chapter["self"], "https://example.com/api/books/1/relationships/first_chapter");
+
+    for chapter in included.unwrap() {
+        let self_link = format!("https://example.com/api/chapters/{}", chapter.id);
+        assert_eq!(chapter.links.unwrap()["self"], self_link.as_str());
+    }
+
+    // Without a base URL there are no links
+    let (resource, _) = fellowship().to_jsonapi_resource();
+    assert!(resource.links.is_none());
+    assert!(resource.relationships.unwrap().values().all(|r| r.links.is_none()));
+}
+
+#[test]
+fn meta_from_model() {
+    let review = Review {
+        id: "1".into(),
+        stars: 5,
+        created_at: "2024-01-01".into(),
+        book: fellowship(),
+    };
+    let (resource, included) = review.to_jsonapi_resource();
+    assert_eq!(resource.meta.as_ref().unwrap()["createdAt"], "2024-01-01");
+    assert!(included.as_ref().unwrap().iter().all(|r| r.meta.is_none()));
+
+    assert_eq!(Review::from_jsonapi_resource(&resource, &included).unwrap(), review);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Shelfmark {
+    id: String,
+    code: String,
+    books: Vec<Book>,
+}
+jsonapi_model!(Shelfmark; "shelfmarks"; has many books; {
+    fn link_templates() -> Option<LinkTemplates> {
+        Some(LinkTemplates {
+            resource: Some("/library/marks/{id}".into()),
+            relationship: None,
+            ..Default::default()
+        })
+    }
+});
+
+#[test]
+fn links_from_templates() {
+    let ctx = ConversionContext {
+        link_templates: Some(LinkTemplates {
+            resource: Some("/v2/{type}/{id}".into()),
+            relationship: Some("/v2/{type}/{id}/links/{name}".into()),
+            related: None,
+        }),
+        naming: NamingPolicy::KebabCase,
+        ..Default::default()
+    };
+    let (resource, _) = fellowship().try_to_jsonapi_resource_with(&ctx).unwrap();
+    assert_eq!(resource.links.unwrap()["self"], "/v2/books/1");
+    let relationships = resource.relationships.unwrap();
+    let chapters = relationships["chapters"].links.as_ref().unwrap();
+    assert_eq!(chapters.len(), 1);
+    assert_eq!(chapters["self"], "/v2/books/1/links/chapters");
+    let first_chapter = relationships["first-chapter"].links.as_ref().unwrap();
+    assert_eq!(first_chapter["self"], "/v2/books/1/links/first-chapter");
+
+    // The templates of a model win over those of the context, and the models
+    // it includes keep using the context
+    let mark = Shelfmark { id: "7".into(), code: "FIC".into(), books: vec![fellowship()] };
+    let ctx = ConversionContext {
+        base_url: Some("https://example.com".into()),
+        ..Default::default()
+    };
+    let (resource, included) = mark.try_to_jsonapi_resource_with(&ctx).unwrap();
+    assert_eq!(resource.links.unwrap()["self"], "https://example.com/library/marks/7");
+    let books = resource.relationships.unwrap()["books"].links.clone().unwrap();
+    assert_eq!(books.keys().collect::<Vec<_>>(), vec!["related"]);
+    assert_eq!(books["related"], "https://example.com/shelfmarks/7/books");
+    let book = included.unwrap().into_iter().find(|r| r._type == "books").unwrap();
+    assert_eq!(book.links.unwrap()["self"], "https://example.com/books/1");
+}
+
+#[test]
+fn links_encode_ids() {
+    let templates = LinkTemplates::default();
+    let links = templates.resource_links(None, "books", "a/b c?d").unwrap();
+    assert_eq!(links["self"], "/books/a%2Fb%20c%3Fd");
+
+    // Values are substituted once, placeholders in them are left alone
+    let links = templates.relationship_links(Some("https://example.com/"), "books", "{name}", "ch\u{e4}pters").unwrap();
+    assert_eq!(links["self"], "https://example.com/books/%7Bname%7D/relationships/ch%C3%A4pters");
+    assert_eq!(links["related"], "https://example.com/books/%7Bname%7D/ch%C3%A4pters");
+
+    let templates = LinkTemplates { resource: Some("/{kind}/{id}".into()), ..Default::default() };
+    assert_eq!(templates.resource_links(None, "books", "1").unwrap()["self"], "/{kind}/1");
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Film {
+    id: String,
+    minutes: u32,
+}
+jsonapi_model!(Film; "films");
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Adaptable {
+    Book(Book),
+    Film(Film),
+}
+jsonapi_polymorphic!(Adaptable; "books" => Book, "films" => Film);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Franchise {
+    id: String,
+    name: String,
+    origin: Adaptable,
+    works: Vec<Adaptable>,
+}
+jsonapi_model!(Franchise; "franchises"; has one origin; has many works);
+
+#[test]
+fn polymorphic_relationships_round_trip() {
+    let franchise = Franchise {
+        id: "1".into(),
+        name: "Middle-earth".into(),
+        origin: Adaptable::Book(fellowship()),
+        works: vec![
+            Adaptable::Film(Film { id: "1".into(), minutes: 178 }),
+            Adaptable::Book(fellowship()),
+        ],
+    };
+
+    let doc = franchise.to_jsonapi_document();
+    let json = serde_json::to_value(&doc).unwrap();
+    assert_eq!(json["data"]["relationships"]["origin"]["data"]["type"], "books");
+    assert_eq!(json["data"]["relationships"]["works"]["data"][0]["type"], "films");
+    assert_eq!(json["data"]["relationships"]["works"]["data"][1]["type"], "books");
+
+    let doc: DocumentData = serde_json::from_value(json).unwrap();
+    assert_eq!(Franchise::from_jsonapi_document(&doc).unwrap(), franchise);
+}
+
+#[test]
+fn polymorphic_relationship_with_unknown_type() {
+    let franchise = Franchise {
+        id: "1".into(),
+        name: "Middle-earth".into(),
+        origin: Adaptable::Film(Film { id: "1".into(), minutes: 178 }),
+        works: vec![],
+    };
+    let (mut resource, included) = franchise.to_jsonapi_resource();
+    let mut included = included.unwrap();
+    included[0]._type = "games".into();
+    if let Some(IdentifierData::Single(ref mut identifier)) =
+        resource.relationships.as_mut().unwrap().get_mut("origin").unwrap().data
+    {
+        identifier._type = "games".into();
+    }
+
+    let err = Franchise::from_jsonapi_resource(&resource, &Some(included)).unwrap_err();
+    assert!(err.to_string().contains("'games'"), "{}", err);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Loan {
+    id: u32,
+    reader: ResourceIdentifier,
+    volumes: Vec<jsonapi::id::Id<Volume>>,
+}
+jsonapi_model!(Loan; "loans"; has one reader; has many volumes);
+
+#[test]
+fn identifier_relationships() {
+    use jsonapi::id::Id;
+
+    let loan = Loan {
+        id: 3,
+        reader: ResourceIdentifier { _type: "readers".into(), id: "frodo".into() },
+        volumes: vec![Id::new(11), Id::new(-4)],
+    };
+
+    let doc = loan.to_jsonapi_document();
+    let json = serde_json::to_value(&doc).unwrap();
+    assert_eq!(json["included"], serde_json::json!([]));
+    assert_eq!(json["data"]["relationships"]["reader"]["data"]["id"], "frodo");
+    assert_eq!(json["data"]["relationships"]["volumes"]["data"][1]["id"], "-4");
+
+    // Linkage deserializes without anything included
+    let doc: DocumentData = serde_json::from_value(json).unwrap();
+    assert_eq!(Loan::from_jsonapi_document(&doc).unwrap(), loan);
+
+    // and ignores the related resources when they are included
+    let (resource, _) = loan.to_jsonapi_resource();
+    let included = vec![Resource {
+        _type: "readers".into(),
+        id: "frodo".into(),
+        ..Default::default()
+    }];
+    assert_eq!(Loan::from_jsonapi_resource(&resource, &Some(included)).unwrap(), loan);
+}
+
+#[test]
+fn identifier_relationships_with_invalid_id() {
+    let loan = Loan {
+        id: 3,
+        reader: ResourceIdentifier { _type: "readers".into(), id: "frodo".into() },
+        volumes: vec![],
+    };
+    let (mut resource, _) = loan.to_jsonapi_resource();
+    resource.relationships.as_mut().unwrap().get_mut("volumes").unwrap().data =
+        Some(IdentifierData::Multiple(vec![ResourceIdentifier { _type: "volumes".into(), id: "eleven".into() }]));
+
+    let err = Loan::from_jsonapi_resource(&resource, &None).unwrap_err();
+    assert!(err.to_string().contains("eleven"), "{}", err);
+}
+
+#[test]
+fn identifiers_of_models() {
+    use jsonapi::id::Id;
+
+    let volume = Volume { id: 11, title: "The Two Towers".into() };
+    let id = Id::of(&volume);
+    assert_eq!(id, Id::new(11));
+    assert_eq!(id.type_name(), "volumes");
+    assert_eq!(serde_json::to_value(&id).unwrap(), serde_json::json!({ "type": "volumes", "id": "11" }));
+
+    let parsed: Id<Volume> = serde_json::from_value(serde_json::json!({ "type": "volumes", "id": "11" })).unwrap();
+    assert_eq!(parsed, id);
+    // The type and id must be those of the model
+    let parsed: Result<Id<Volume>, _> = serde_json::from_value(serde_json::json!({ "type": "books", "id": "11" }));
+    assert!(parsed.is_err());
+    let parsed: Result<Id<Volume>, _> = serde_json::from_value(serde_json::json!({ "type": "volumes", "id": "xi" }));
+    assert!(parsed.is_err());
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Edition {
+    id: String,
+    year: u16,
+    foreword: Option<Chapter>,
+    previous: Option<Box<Edition>>,
+    book: std::rc::Rc<Book>,
+    shelf_chapter: std::sync::Arc<Chapter>,
+}
+jsonapi_model!(Edition; "editions"; has one foreword, previous, book, shelf_chapter);
+
+fn edition(id: &str, year: u16, previous: Option<Edition>) -> Edition {
+    Edition {
+        id: id.into(),
+        year,
+        foreword: None,
+        previous: previous.map(Box::new),
+        book: std::rc::Rc::new(fellowship()),
+        shelf_chapter: std::sync::Arc::new(Chapter { id: "9".into(), title: "Appendix".into(), ordering: 9 }),
+    }
+}
+
+#[test]
+fn optional_to_one_relationships() {
+    let mut second = edition("2", 1966, Some(edition("1", 1954, None)));
+    second.foreword = Some(Chapter { id: "0".into(), title: "Foreword".into(), ordering: 0 });
+
+    let doc = second.to_jsonapi_document();
+    let json = serde_json::to_value(&doc).unwrap();
+    assert_eq!(json["data"]["relationships"]["foreword"]["data"]["id"], "0");
+    assert_eq!(json["data"]["relationships"]["previous"]["data"]["id"], "1");
+
+    let doc: DocumentData = serde_json::from_value(json).unwrap();
+    let again = Edition::from_jsonapi_document(&doc).unwrap();
+    assert_eq!(again.foreword, second.foreword);
+    assert_eq!(again.book, second.book);
+    assert_eq!(again.shelf_chapter, second.shelf_chapter);
+    // The first edition refers to no previous edition
+    let first = again.previous.unwrap();
+    assert_eq!(first.year, 1954);
+    assert_eq!(first.previous, None);
+}
+
+#[test]
+fn optional_to_one_serializes_none_as_null() {
+    let first = edition("1", 1954, None);
+    let doc = first.to_jsonapi_document();
+    let json = serde_json::to_value(&doc).unwrap();
+    assert_eq!(json["data"]["relationships"]["foreword"]["data"], serde_json::Value::Null);
+    assert_eq!(json["data"]["relationships"]["previous"]["data"], serde_json::Value::Null);
+
+    let doc: DocumentData = serde_json::from_value(json).unwrap();
+    assert_eq!(Edition::from_jsonapi_document(&doc).unwrap(), first);
+}
+
+#[test]
+fn optional_to_one_patched_to_none() {
+    let mut second = edition("2", 1966, None);
+    second.foreword = Some(Chapter { id: "0".into(), title: "Foreword".into(), ordering: 0 });
+    let (mut before, _) = second.to_jsonapi_resource();
+    second.foreword = None;
+    let (after, _) = second.to_jsonapi_resource();
+
+    let patchset = before.diff(after.clone()).unwrap();
+    assert_eq!(patchset.patches.len(), 1);
+    let patched = before.patch(patchset).unwrap();
+    assert_eq!(patched, after);
+    let json = serde_json::to_value(&patched).unwrap();
+    assert_eq!(json["relationships"]["foreword"], serde_json::json!({ "data": null }));
+}
+
+#[test]
+fn optional_to_one_missing_from_included() {
+    let mut first = edition("1", 1954, None);
+    first.foreword = Some(Chapter { id: "0".into(), title: "Foreword".into(), ordering: 0 });
+    let (resource, included) = first.to_jsonapi_resource();
+    let included: Vec<Resource> = included.unwrap().into_iter().filter(|r| r.id != "0").collect();
+
+    let again = Edition::from_jsonapi_resource(&resource, &Some(included)).unwrap();
+    assert_eq!(again.foreword, None);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Source {
+    id: u32,
+}
+jsonapi_model!(Source; "sources");
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Tag {
+    id: String,
+}
+jsonapi_model!(Tag; "tags");
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Anthology {
+    id: String,
+    chapters: std::collections::VecDeque<Chapter>,
+    appendices: Box<[Chapter]>,
+    sources: std::collections::BTreeSet<jsonapi::id::Id<Source>>,
+    tags: Option<std::collections::HashSet<jsonapi::id::Id<Tag>>>,
+}
+jsonapi_model!(Anthology; "anthologies"; has many chapters, appendices, sources, tags);
+
+#[test]
+fn has_many_with_other_collections() {
+    use jsonapi::id::Id;
+
+    let anthology = Anthology {
+        id: "1".into(),
+        chapters: vec![
+            Chapter { id: "1".into(), title: "One".into(), ordering: 1 },
+            Chapter { id: "2".into(), title: "Two".into(), ordering: 2 },
+        ].into(),
+        appendices: vec![Chapter { id: "3".into(), title: "Maps".into(), ordering: 3 }].into_boxed_slice(),
+        sources: vec![Id::new(2), Id::new(1)].into_iter().collect(),
+        tags: Some(vec![Id::new("epic".to_string())].into_iter().collect()),
+    };
+
+    let (resource, included) = anthology.to_jsonapi_resource();
+    assert_eq!(included.as_ref().unwrap().len(), 3);
+    let relationships = resource.relationships.as_ref().unwrap();
+    match relationships["sources"].data {
+        Some(IdentifierData::Multiple(ref sources)) => {
+            let ids: Vec<&str> = sources.iter().map(|source| source.id.as_str()).collect();
+            assert_eq!(ids, vec!["1", "2"]);
+        }
+        _ => assert!(false),
+    }
+
+    assert_eq!(Anthology::from_jsonapi_resource(&resource, &included).unwrap(), anthology);
+}
+
+#[test]
+fn has_many_models_can_be_changed_in_place() {
+    use jsonapi::array::{JsonApiArray, JsonApiArrayMut};
+
+    let mut chapters: std::collections::VecDeque<Chapter> = vec![
+        Chapter { id: "1".into(), title: "One".into(), ordering: 1 },
+        Chapter { id: "2".into(), title: "Two".into(), ordering: 2 },
+    ].into();
+    for chapter in chapters.get_models_mut() {
+        chapter.ordering *= 10;
+    }
+    let orderings: Vec<_> = chapters.get_models().map(|chapter| chapter.ordering).collect();
+    assert_eq!(orderings, vec![10, 20]);
+
+    let mut appendices = Some(vec![Chapter { id: "3".into(), title: "Maps".into(), ordering: 3 }]);
+    appendices.get_models_mut().for_each(|chapter| chapter.title = "Indexes".into());
+    assert_eq!(appendices.get_models().next().unwrap().title, "Indexes");
+    let mut none: Option<Vec<Chapter>> = None;
+    assert_eq!(none.get_models_mut().count(), 0);
+}
+
+#[cfg(feature = "smallvec")]
+#[test]
+fn has_many_with_smallvec() {
+    extern crate smallvec;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Pamphlet {
+        id: String,
+        chapters: smallvec::SmallVec<[Chapter; 2]>,
+    }
+    jsonapi_model!(Pamphlet; "pamphlets"; has many chapters);
+
+    let pamphlet = Pamphlet {
+        id: "1".into(),
+        chapters: smallvec::SmallVec::from_vec(vec![Chapter { id: "1".into(), title: "One".into(), ordering: 1 }]),
+    };
+    let (resource, included) = pamphlet.to_jsonapi_resource();
+    assert_eq!(Pamphlet::from_jsonapi_resource(&resource, &included).unwrap(), pamphlet);
+}
+
+#[cfg(feature = "indexmap")]
+#[test]
+fn has_many_with_index_set() {
+    extern crate indexmap;
+    use jsonapi::id::Id;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Entry {
+        id: u32,
+    }
+    jsonapi_model!(Entry; "entries");
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Catalogue {
+        id: String,
+        entries: indexmap::IndexSet<Id<Entry>>,
+    }
+    jsonapi_model!(Catalogue; "catalogues"; has many entries);
+
+    let catalogue = Catalogue {
+        id: "1".into(),
+        entries: vec![Id::new(3), Id::new(1)].into_iter().collect(),
+    };
+    let (resource, included) = catalogue.to_jsonapi_resource();
+    assert_eq!(Catalogue::from_jsonapi_resource(&resource, &included).unwrap(), catalogue);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Footnote {
+    id: String,
+    text: String,
+    marker: Option<String>,
+}
+jsonapi_model!(Footnote; "footnotes");
+
+fn footnote_resource() -> Resource {
+    Footnote { id: "1".into(), text: "See appendix".into(), marker: None }
+        .to_jsonapi_resource()
+        .0
+}
+
+#[test]
+fn strict_context_accepts_conforming_documents() {
+    let doc = fellowship().to_jsonapi_document();
+    let json = serde_json::to_string(&doc).unwrap();
+    let doc: DocumentData = serde_json::from_str(&json).unwrap();
+    assert_eq!(Book::from_jsonapi_document_with(&doc, &ConversionContext::strict()).unwrap(), fellowship());
+}
+
+#[test]
+fn strict_type_check() {
+    let mut resource = footnote_resource();
+    resource._type = "endnotes".into();
+
+    match Footnote::from_jsonapi_resource(&resource, &None) {
+        Ok(_) => assert!(false),
+        Err(err) => match err {
+            Error::Conversion(ConversionError::ResourceTypeMismatch(ref resource, ref expected)) => {
+                assert_eq!(resource, "endnotes/1");
+                assert_eq!(expected, "footnotes");
+            }
+            _ => assert!(false),
+        },
+    }
+
+    let ctx = ConversionContext { check_type: false, ..Default::default() };
+    assert!(Footnote::from_jsonapi_resource_with(&resource, &None, &ctx).is_ok());
+}
+
+#[test]
+fn type_check_in_relationships() {
+    assert_eq!(Book::jsonapi_type_name(), "books");
+    assert_eq!(<Box<Chapter> as JsonApiModel>::jsonapi_type_name(), "chapters");
+
+    let (mut resource, included) = fellowship().to_jsonapi_resource();
+    let mut included = included.unwrap();
+    for related in included.iter_mut().filter(|r| r.id == "2") {
+        related._type = "pages".into();
+    }
+    if let Some(IdentifierData::Multiple(ref mut chapters)) =
+        resource.relationships.as_mut().unwrap().get_mut("chapters").unwrap().data
+    {
+        chapters[1]._type = "pages".into();
+    }
+
+    match Book::from_jsonapi_resource(&resource, &Some(included)) {
+        Ok(_) => assert!(false),
+        Err(err) => match err {
+            Error::Conversion(ConversionError::ResourceTypeMismatch(ref resource, ref expected)) => {
+                assert_eq!(resource, "pages/2");
+                assert_eq!(expected, "chapters");
+            }
+            _ => assert!(false),
+        },
+    }
+}
+
+#[test]
+fn strict_unresolved_relationships() {
+    let (resource, included) = fellowship().to_jsonapi_resource();
+    let included: Vec<Resource> = included.unwrap().into_iter().filter(|r| r.id != "2").collect();
+    let ctx = ConversionContext { require_included: true, ..Default::default() };
+
+    match Book::from_jsonapi_resource_with(&resource, &Some(included), &ctx) {
+        Ok(_) => assert!(false),
+        Err(err) => match err {
+            Error::Conversion(ConversionError::UnresolvedRelationship(ref resource, ref relationship, ref related)) => {
+                assert_eq!(resource, "books/1");
+                assert_eq!(relationship, "chapters");
+                assert_eq!(related, "chapters/2");
+            }
+            _ => assert!(false),
+        },
+    }
+
+    // Without any included resources at all
+    assert!(Book::from_jsonapi_resource_with(&resource, &None, &ctx).is_err());
+}
+
+#[test]
+fn strict_unknown_attributes() {
+    let mut resource = footnote_resource();
+    resource.attributes.insert("colour".into(), "red".into());
+    assert!(Footnote::from_jsonapi_resource(&resource, &None).is_ok());
+
+    let ctx = ConversionContext { deny_unknown_attributes: true, ..Default::default() };
+    match Footnote::from_jsonapi_resource_with(&resource, &None, &ctx) {
+        Ok(_) => assert!(false),
+        Err(err) => match err {
+            Error::Conversion(ConversionError::UnknownAttribute(ref resource, ref attribute)) => {
+                assert_eq!(resource, "footnotes/1");
+                assert_eq!(attribute, "colour");
+            }
+            _ => assert!(false),
+        },
+    }
+}
+
+#[test]
+fn strict_missing_attributes() {
+    let mut resource = footnote_resource();
+    resource.attributes.remove("marker");
+    assert!(Footnote::from_jsonapi_resource(&resource, &None).is_ok());
+
+    let mut ctx = ConversionContext { deny_missing_attributes: true, ..Default::default() };
+    match Footnote::from_jsonapi_resource_with(&resource, &None, &ctx) {
+        Ok(_) => assert!(false),
+        Err(err) => match err {
+            Error::Conversion(ConversionError::MissingAttribute(ref resource, ref attribute)) => {
+                assert_eq!(resource, "footnotes/1");
+                assert_eq!(attribute, "marker");
+            }
+            _ => assert!(false),
+        },
+    }
+
+    ctx.allow_missing_optional_attributes = true;
+    let footnote = Footnote::from_jsonapi_resource_with(&resource, &None, &ctx).unwrap();
+    assert_eq!(footnote.marker, None);
+
+    resource.attributes.remove("text");
+    match Footnote::from_jsonapi_resource_with(&resource, &None, &ctx) {
+        Ok(_) => assert!(false),
+        Err(err) => match err {
+            Error::Conversion(ConversionError::MissingAttribute(_, ref attribute)) => assert_eq!(attribute, "text"),
+            _ => assert!(false),
+        },
+    }
+}
+
+fn invalid_document_pointer<T: std::fmt::Debug>(result: jsonapi::errors::Result<T>) -> String {
+    match result {
+        Ok(_) => panic!("expected an error"),
+        Err(err) => match err {
+            Error::Parse(ParseError::InvalidMember(ref pointer, _)) => pointer.clone(),
+            _ => panic!("unexpected error {}", err),
+        },
+    }
+}
+
+#[test]
+fn invalid_attributes_point_at_their_member() {
+    let json = r#"{"data": [
+        {"type": "chapters", "id": "1", "attributes": {"title": "A Long-expected Party", "ordering": 1}},
+        {"type": "chapters", "id": "2", "attributes": {"title": "The Shadow of the Past", "ordering": "two"}}
+    ]}"#;
+    let doc: DocumentData = serde_json::from_str(json).unwrap();
+    let result = CollectionDocument::<Chapter>::from_document_data_with(doc, &ConversionContext::default());
+    assert_eq!(invalid_document_pointer(result), "/data/1/attributes/ordering");
+
+    let json = r#"{"data": {"type": "chapters", "id": "1", "attributes": {"ordering": 1}}}"#;
+    let doc: DocumentData = serde_json::from_str(json).unwrap();
+    assert_eq!(invalid_document_pointer(Chapter::from_jsonapi_document(&doc)), "/data");
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Dimensions {
+        width: u32,
+        height: u32,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Map {
+        id: String,
+        sizes: Vec<Dimensions>,
+    }
+    jsonapi_model!(Map; "maps");
+
+    let json = r#"{"data": {"type": "maps", "id": "1", "attributes": {
+        "sizes": [{"width": 1, "height": 2}, {"width": 1, "height": -2}]
+    }}}"#;
+    let doc: DocumentData = serde_json::from_str(json).unwrap();
+    assert_eq!(invalid_document_pointer(Map::from_jsonapi_document(&doc)), "/data/attributes/sizes/1/height");
+}
+
+#[test]
+fn invalid_included_resources_point_at_their_member() {
+    let (resource, included) = fellowship().to_jsonapi_resource();
+    let mut included = included.unwrap();
+    let position = included.iter().position(|r| r.id == "2").unwrap();
+    included[position].attributes.insert("ordering".into(), "second".into());
+
+    let result = Book::from_jsonapi_resource(&resource, &Some(included.clone()));
+    assert_eq!(invalid_document_pointer(result), format!("/included/{}/attributes/ordering", position));
+
+    // Without any included resources relationships are not read, so the
+    // missing field is reported at the resource
+    let result = Book::from_jsonapi_resource(&resource, &None);
+    assert_eq!(invalid_document_pointer(result), "");
+
+    let mut resource = resource;
+    resource.relationships.as_mut().unwrap().remove("chapters");
+    let included: Vec<Resource> = included.into_iter().filter(|r| r.id != "1").collect();
+    let result = Book::from_jsonapi_resource(&resource, &Some(included));
+    assert_eq!(invalid_document_pointer(result), "/relationships/first_chapter/data");
+}
+
+#[test]
+fn invalid_documents_convert_into_errors_with_pointers() {
+    let json = r#"{"data": {"type": "chapters", "id": "1", "attributes": {"title": 1, "ordering": 1}}}"#;
+    let doc: DocumentData = serde_json::from_str(json).unwrap();
+    let error = jsonapi::api::JsonApiError::from(Chapter::from_jsonapi_document(&doc).unwrap_err());
+    assert_eq!(error.status, Some("400".into()));
+    assert_eq!(error.source.unwrap().pointer, Some("/data/attributes/title".into()));
+    assert!(error.detail.unwrap().contains("invalid type: integer `1`, expected a string"));
+}
@@ -1,3 +1,4 @@
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
 extern crate jsonapi;
 extern crate env_logger;
 
@@ -553,3 +554,24 @@ fn can_generate_page_fields() {
 
     assert_eq!(query_string, "page[size]=5&page[number]=10");
 }
+
+#[test]
+fn try_from_params_rejects_invalid_pages() {
+    use jsonapi::errors::{Error, QueryError};
+    let _ = env_logger::try_init();
+
+    let query = Query::try_from_params("include=author&page[number]=3&page[size]=10").unwrap();
+    assert_eq!(query, Query::from_params("include=author&page[number]=3&page[size]=10"));
+
+    match Query::try_from_params("page[number]=2&page[size]=ten") {
+        Err(Error::Query(QueryError::InvalidPage(ref parameter, ref value))) => {
+            assert_eq!(parameter, "page[size]");
+            assert_eq!(value, "ten");
+        }
+        _ => assert!(false),
+    }
+
+    // The lenient parser falls back to the default page instead
+    let query = Query::from_params("page[number]=2&page[size]=ten");
+    assert_eq!(query.page.unwrap().size, 0);
+}